@@ -1,13 +1,57 @@
+use std::sync::Arc;
+
 use library::prelude::*;
-use structopt::StructOpt;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::from_args();
-    let api_key = get_api_key(&args.key_file).expect(MISSING_AUTH_MESSAGE);
-    let client = DefaultClient::create(api_key);
-    execute_query(client, std::io::stdout(), args).await?;
-    Ok(())
+    env_logger::init();
+    let args = Args::load()?;
+    if let Some(Command::Cache(CacheCommand::Clear)) = &args.command {
+        if let Some(cache) = args.disk_cache() {
+            cache.clear()?;
+        }
+        return Ok(());
+    }
+
+    if let Some(dir) = &args.replay {
+        return run(ReplayClient::new(dir), args).await;
+    }
+
+    if let Some(dir) = &args.local_dir {
+        let client = LocalGitClient::create(dir, args.local_options());
+        return run(client, args).await;
+    }
+
+    let api_key = get_api_key_with(&args.auth_options()).expect(MISSING_AUTH_MESSAGE);
+    let builder = args.client_builder(api_key);
+    match args.disk_cache() {
+        Some(cache) => {
+            let cache: Arc<dyn ResponseCache> = Arc::new(cache);
+            let client = builder.cache(cache.clone()).build()?;
+            let client = CachingClient::new(client, cache, args.cache_ttl());
+            run(client, args).await
+        }
+        None => run(builder.build()?, args).await,
+    }
+}
+
+/// record the client's results if requested
+async fn run<C: GithubClient + 'static>(client: C, args: Args) -> Result<()> {
+    match args.record.clone() {
+        Some(dir) => report(RecordingClient::new(client, dir), args).await,
+        None => report(client, args).await,
+    }
+}
+
+/// write the report to stdout, or serve reports until interrupted
+async fn report<C: GithubClient + 'static>(client: C, args: Args) -> Result<()> {
+    let options = args.analysis_options()?;
+    if let Some(Command::Serve { address }) = args.command {
+        let listener = tokio::net::TcpListener::bind(address).await?;
+        return serve(listener, client, options).await;
+    }
+    let (queries, named) = (args.queries(), args.named_repositories().await?);
+    execute_queries_with(client, std::io::stdout(), queries, named, &options).await
 }
 
 const MISSING_AUTH_MESSAGE: &str = r#"
@@ -15,4 +59,9 @@ No API key provided
 Go to https://github.com/settings/tokens to generate a token, then provide it via
  - the BUS_FACTOR_AUTH environment variable
  - a file passed via the --key_file argument
+ - the GH_TOKEN or GITHUB_TOKEN environment variables, or GH_ENTERPRISE_TOKEN for
+   Github Enterprise Server
+ - the gh CLI, after `gh auth login`
+ - ~/.netrc, as the password for github.com or api.github.com (or the API's host)
+ - the Secret Service keyring, under `service bus-factor host github.com`
 "#;
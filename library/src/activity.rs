@@ -0,0 +1,65 @@
+use chrono::{DateTime, Duration, Utc};
+
+use crate::model::{ActivityStatus, LeadActivity};
+
+/// Thresholds for how long a lead contributor can go without committing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ActivityOptions {
+    /// without a commit for this long, the lead is dormant
+    pub dormant_after: Duration,
+    /// without a commit for this long, the lead has probably left the project
+    pub departed_after: Duration,
+}
+
+impl Default for ActivityOptions {
+    fn default() -> Self {
+        Self {
+            dormant_after: Duration::days(180),
+            departed_after: Duration::days(365),
+        }
+    }
+}
+
+impl ActivityOptions {
+    pub fn classify(&self, last_commit: Option<DateTime<Utc>>, now: DateTime<Utc>) -> LeadActivity {
+        let status = match last_commit.map(|last_commit| now - last_commit) {
+            None => ActivityStatus::Unknown,
+            Some(idle) if idle >= self.departed_after => ActivityStatus::Departed,
+            Some(idle) if idle >= self.dormant_after => ActivityStatus::Dormant,
+            Some(_) => ActivityStatus::Active,
+        };
+        LeadActivity {
+            last_commit,
+            status,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status_after(days: i64) -> ActivityStatus {
+        let now = "2021-06-30T00:00:00Z".parse().unwrap();
+        let last_commit = now - Duration::days(days);
+        ActivityOptions::default()
+            .classify(Some(last_commit), now)
+            .status
+    }
+
+    #[test]
+    fn classifies_by_time_since_last_commit() {
+        assert_eq!(status_after(1), ActivityStatus::Active);
+        assert_eq!(status_after(179), ActivityStatus::Active);
+        assert_eq!(status_after(180), ActivityStatus::Dormant);
+        assert_eq!(status_after(364), ActivityStatus::Dormant);
+        assert_eq!(status_after(548), ActivityStatus::Departed);
+    }
+
+    #[test]
+    fn no_commit_is_unknown() {
+        let activity = ActivityOptions::default().classify(None, Utc::now());
+        assert_eq!(activity.status, ActivityStatus::Unknown);
+        assert_eq!(activity.last_commit, None);
+    }
+}
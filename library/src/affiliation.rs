@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::OnceCell;
+
+use crate::{
+    client::GithubClient,
+    errors::{Error, ErrorKind},
+    model::{Contributor, OrganizationSummary},
+};
+
+/// profiles are only looked up for this many of the largest contributors of each repository, the
+/// rest count as unaffiliated
+const MAX_LOOKUPS: usize = 30;
+
+/// Works out which company or organization each contributor belongs to
+///
+/// Manual overrides come first, then the `company` field of the Github profile, and optionally a
+/// user's public organization membership when they belong to exactly one. Overrides are loaded
+/// from a TOML file of the form:
+///
+/// ```toml
+/// [affiliations]
+/// "Acme Corp" = ["alice", "bob@acme.example"]
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Affiliations {
+    overrides: HashMap<String, String>,
+    use_organizations: bool,
+    /// lookups are shared between repositories, since contributors often overlap, and one in
+    /// flight is awaited rather than repeated
+    resolved: Arc<Mutex<HashMap<String, Lookup>>>,
+}
+
+/// a login's affiliation, once its profile has been looked up
+type Lookup = Arc<OnceCell<Option<String>>>;
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AffiliationFile {
+    affiliations: HashMap<String, Vec<String>>,
+}
+
+impl Affiliations {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, Error> {
+        let file: AffiliationFile = toml::from_str(contents)?;
+        let mut affiliations = Self::default();
+        for (affiliation, members) in file.affiliations {
+            for member in members {
+                affiliations = affiliations.assign(member, &affiliation);
+            }
+        }
+        Ok(affiliations)
+    }
+
+    /// affiliate a login, name or email regardless of what their profile says, ignoring case
+    pub fn assign(mut self, identity: impl AsRef<str>, affiliation: impl Into<String>) -> Self {
+        self.overrides
+            .insert(identity.as_ref().to_lowercase(), affiliation.into());
+        self
+    }
+
+    /// fall back to a user's public organization when their profile has no company
+    pub fn use_organizations(mut self, enable: bool) -> Self {
+        self.use_organizations = enable;
+        self
+    }
+
+    async fn resolve<C: GithubClient>(
+        &self,
+        client: &C,
+        contributor: &Contributor,
+    ) -> Result<Option<String>, Error> {
+        let identities = [&contributor.login, &contributor.name, &contributor.email];
+        let overridden = identities
+            .into_iter()
+            .flatten()
+            .find_map(|identity| self.overrides.get(&identity.to_lowercase()));
+        if let Some(affiliation) = overridden {
+            return Ok(Some(affiliation.clone()));
+        }
+
+        // anonymous contributors have no profile
+        let Some(login) = &contributor.login else {
+            return Ok(None);
+        };
+        let cell = self
+            .resolved
+            .lock()
+            .unwrap()
+            .entry(login.to_lowercase())
+            .or_default()
+            .clone();
+        // a failed lookup leaves the cell empty, for the next caller to try again
+        let affiliation = cell.get_or_try_init(|| self.lookup(client, login)).await?;
+        Ok(affiliation.clone())
+    }
+
+    async fn lookup<C: GithubClient>(
+        &self,
+        client: &C,
+        login: &str,
+    ) -> Result<Option<String>, Error> {
+        let profile = match client.get_profile(login).await {
+            // the login may come from an identity alias rather than a real account, and local
+            // repositories have no profiles; only the override file applies then
+            Err(Error(ErrorKind::NotFound(_) | ErrorKind::Unsupported(_), _)) => return Ok(None),
+            result => result?,
+        };
+        if let Some(company) = profile.company.as_deref().and_then(normalize_company) {
+            return Ok(Some(company));
+        }
+
+        if self.use_organizations {
+            // several memberships don't say which one they work for
+            if let [org] = client.list_organizations(login).await?.as_slice() {
+                return Ok(Some(org.clone()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// the share of contributions made by the most common affiliation, or `None` if no
+    /// affiliation is known
+    pub async fn summarize<C: GithubClient>(
+        &self,
+        client: &C,
+        contributors: &[Contributor],
+    ) -> Result<Option<OrganizationSummary>, Error> {
+        let mut largest: Vec<&Contributor> = contributors.iter().collect();
+        largest.sort_by_key(|c| std::cmp::Reverse(c.contributions));
+        largest.truncate(MAX_LOOKUPS);
+
+        let lookups = largest.iter().map(|c| self.resolve(client, c));
+        let affiliations = futures::future::try_join_all(lookups).await?;
+
+        // grouped ignoring case, reported as first seen
+        let mut groups: Vec<(String, u64)> = vec![];
+        for (contributor, affiliation) in largest.iter().zip(affiliations) {
+            let Some(affiliation) = affiliation else {
+                continue;
+            };
+            let existing = groups
+                .iter_mut()
+                .find(|(name, _)| name.eq_ignore_ascii_case(&affiliation));
+            match existing {
+                Some((_, total)) => *total += contributor.contributions,
+                None => groups.push((affiliation, contributor.contributions)),
+            }
+        }
+
+        let total: u64 = contributors.iter().map(|c| c.contributions).sum();
+        let affiliated: u64 = groups.iter().map(|(_, contributions)| contributions).sum();
+        let lead = groups
+            .into_iter()
+            .max_by(|(a, a_total), (b, b_total)| a_total.cmp(b_total).then_with(|| b.cmp(a)));
+        Ok(lead.map(|(name, contributions)| OrganizationSummary {
+            lead_organization: name,
+            percentage: contributions as f64 / total as f64,
+            unaffiliated: (total - affiliated) as f64 / total as f64,
+        }))
+    }
+}
+
+/// profiles write companies as `@github`, ` GitHub ` and so on
+fn normalize_company(company: &str) -> Option<String> {
+    let company = company.trim().trim_start_matches('@').trim();
+    (!company.is_empty()).then(|| company.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::model::{Profile, Query, Repository};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct MockClient {
+        lookups: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl GithubClient for MockClient {
+        async fn list_repositories<Q>(&self, _query: Q) -> Result<Vec<Repository>, Error>
+        where
+            Q: Into<Query> + Send,
+        {
+            Ok(vec![]) // not used by these tests
+        }
+
+        async fn list_contributors(
+            &self,
+            _repository: &Repository,
+        ) -> Result<Vec<Contributor>, Error> {
+            Ok(vec![]) // not used by these tests
+        }
+
+        async fn get_profile(&self, login: &str) -> Result<Profile, Error> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+            // like a request, lets other lookups start meanwhile
+            tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+            let company = match login {
+                "alice" => Some("@acme"),
+                "bob" => Some(" Acme "),
+                "carol" => Some("Initech"),
+                "ghost" => bail!(ErrorKind::NotFound("users/ghost".into())),
+                _ => None,
+            };
+            Ok(Profile {
+                login: login.into(),
+                company: company.map(str::to_owned),
+            })
+        }
+
+        async fn list_organizations(&self, login: &str) -> Result<Vec<String>, Error> {
+            Ok(match login {
+                "dave" => vec!["hooli".into()],
+                _ => vec!["one".into(), "two".into()],
+            })
+        }
+    }
+
+    fn contributor(login: &str, contributions: u64) -> Contributor {
+        Contributor {
+            login: Some(login.into()),
+            contributions,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn normalizes_companies() {
+        assert_eq!(normalize_company(" @github "), Some("github".into()));
+        assert_eq!(
+            normalize_company("GitHub, Inc."),
+            Some("GitHub, Inc.".into())
+        );
+        assert_eq!(normalize_company(" @ "), None);
+    }
+
+    #[tokio::test]
+    async fn groups_contributions_by_company() {
+        let contributors = [
+            contributor("alice", 40),
+            contributor("bob", 20),
+            contributor("carol", 30),
+            contributor("dave", 10),
+        ];
+        let summary = Affiliations::default()
+            .summarize(&MockClient::default(), &contributors)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(summary.lead_organization, "acme");
+        assert_eq!(summary.percentage, 0.6);
+        assert_eq!(summary.unaffiliated, 0.1);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_a_single_organization() {
+        let contributors = [contributor("dave", 10), contributor("erin", 5)];
+        let summary = Affiliations::default()
+            .use_organizations(true)
+            .summarize(&MockClient::default(), &contributors)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(summary.lead_organization, "hooli");
+        assert_eq!(summary.unaffiliated, 5.0 / 15.0);
+    }
+
+    #[tokio::test]
+    async fn overrides_take_precedence() {
+        let affiliations = Affiliations::from_toml(
+            r#"
+            [affiliations]
+            "Initech" = ["ALICE", "ghost"]
+            "#,
+        )
+        .unwrap();
+        let contributors = [contributor("alice", 40), contributor("carol", 30)];
+        let summary = affiliations
+            .summarize(&MockClient::default(), &contributors)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(summary.lead_organization, "Initech");
+        assert_eq!(summary.percentage, 1.0);
+    }
+
+    #[tokio::test]
+    async fn unknown_accounts_are_unaffiliated() {
+        let contributors = [contributor("ghost", 1), contributor("erin", 1)];
+        let summary = Affiliations::default()
+            .summarize(&MockClient::default(), &contributors)
+            .await
+            .unwrap();
+        assert_eq!(summary, None);
+    }
+
+    #[tokio::test]
+    async fn remembers_lookups() {
+        let client = MockClient::default();
+        let affiliations = Affiliations::default();
+        for _ in 0..3 {
+            affiliations
+                .summarize(&client, &[contributor("alice", 1)])
+                .await
+                .unwrap();
+        }
+        assert_eq!(client.lookups.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn shares_lookups_in_flight() {
+        let client = MockClient::default();
+        let affiliations = Affiliations::default();
+        let contributors = [contributor("alice", 1), contributor("bob", 1)];
+        let summaries = (0..3).map(|_| affiliations.summarize(&client, &contributors));
+        for summary in futures::future::join_all(summaries).await {
+            assert_eq!(summary.unwrap().unwrap().lead_organization, "acme");
+        }
+        assert_eq!(client.lookups.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(Affiliations::from_toml("[affiliation]\nx = [\"y\"]").is_err());
+    }
+}
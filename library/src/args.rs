@@ -1,35 +1,753 @@
+use std::ffi::OsString;
+use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::time::Duration;
 
-use crate::model::Query;
+use chrono::{DateTime, NaiveDate, Utc};
 
-#[derive(StructOpt, Debug, Clone, PartialEq, Eq)]
-#[structopt(name = "bus-factor")]
+use structopt::clap::{self, AppSettings};
+use structopt::StructOpt as _;
+
+use crate::activity::ActivityOptions;
+use crate::affiliation::Affiliations;
+use crate::cache::DiskCache;
+use crate::calculate::{
+    AnalysisOptions, ContributionMetric, IntoQueries, OutputFormat, SortBy, Weighting,
+};
+use crate::client::{DefaultClient, DefaultClientBuilder, DEFAULT_BASE_URL, DEFAULT_USER_AGENT};
+use crate::config::Config;
+use crate::deps::{self, Registry, DEFAULT_REGISTRY_URL};
+use crate::environment::{AuthOptions, AuthSource};
+use crate::errors::Error;
+use crate::filter::BotFilter;
+use crate::identity::Identities;
+use crate::local::LocalGitOptions;
+use crate::model::{ApiKey, Query, Repository, Window};
+use crate::ownership::OwnershipOptions;
+use crate::retry::RetryPolicy;
+use crate::risk::RiskWeights;
+
+/// overrides `--base_url` when it isn't given
+const API_URL_ENV: &str = "BUS_FACTOR_API_URL";
+
+/// Options can also be given in config files, see [`Config`]
+///
+/// Config values are parsed as if they came first on the command line, so that every option
+/// overrides itself and the ones actually given win.
+///
+/// Without a subcommand, repositories are chosen with `--project_count`, `--language` and `--repo`
+/// as before subcommands were added. The shared options come before any subcommand.
+#[derive(StructOpt, Debug, Clone, PartialEq)]
+#[structopt(
+    name = "bus-factor",
+    setting = AppSettings::SubcommandsNegateReqs,
+    setting = AppSettings::AllArgsOverrideSelf
+)]
 pub struct Args {
-    #[structopt(long = "project_count")]  // by default, structopt renames this to "project-count"
-    project_count: u32,
+    /// how many repositories to search for, also the default for `search`
+    #[structopt(
+        short = "n",
+        long = "project_count",  // by default, structopt renames this to "project-count"
+        alias = "project-count",
+        required_unless = "repo"
+    )]
+    project_count: Option<u32>,
 
+    /// search for repositories in this language, can be repeated, also the default for `search`
+    #[structopt(short, long, number_of_values = 1, required_unless = "repo")]
+    language: Vec<String>,
+
+    /// also report on this repository, given as `owner/name`, can be repeated; ignored by
+    /// subcommands
+    #[structopt(long, number_of_values = 1)]
+    repo: Vec<Repository>,
+
+    /// read options from this TOML file, over `./bus-factor.toml` and the user's config
     #[structopt(long)]
-    language: String,
+    config: Option<PathBuf>,
 
-    #[structopt(long = "key_file")]  // for consistency
+    /// the lead's share at which a repository has a bus factor of 1 [default: 0.75]
+    #[structopt(long, parse(try_from_str = parse_share))]
+    threshold: Option<f64>,
+
+    #[structopt(long = "key_file", alias = "key-file")]  // for consistency
     pub key_file: Option<PathBuf>,
+
+    /// only look for the API key here: env, key_file, github_env, gh, netrc or keyring [default:
+    /// each in turn]
+    #[structopt(
+        long = "auth_source",
+        alias = "auth-source",
+        possible_values = AuthSource::VARIANTS
+    )]
+    auth_source: Option<AuthSource>,
+
+    /// root of the Github API, e.g. https://ghe.corp/api/v3 for Github Enterprise Server
+    #[structopt(
+        long = "base_url",
+        alias = "base-url",
+        env = API_URL_ENV,
+        default_value = DEFAULT_BASE_URL
+    )]
+    pub base_url: String,
+
+    /// user agent sent with API requests
+    #[structopt(long = "user_agent", alias = "user-agent", default_value = DEFAULT_USER_AGENT)]
+    user_agent: String,
+
+    /// how many times to retry API requests that fail with a transient error
+    #[structopt(long, default_value = "3")]
+    retries: u32,
+
+    /// maximum number of API requests in flight at once
+    #[structopt(long)]
+    concurrency: Option<usize>,
+
+    /// seconds to wait for a connection to the API to be established
+    #[structopt(long = "connect_timeout", alias = "connect-timeout")]
+    connect_timeout: Option<u64>,
+
+    /// seconds to wait for each API request to complete
+    #[structopt(long)]
+    timeout: Option<u64>,
+
+    /// proxy for API requests, defaults to the HTTPS_PROXY env var
+    #[structopt(long)]
+    proxy: Option<String>,
+
+    /// PEM file of additional certificates to trust, e.g. a corporate CA
+    #[structopt(long = "ca_bundle", alias = "ca-bundle")]
+    ca_bundle: Option<PathBuf>,
+
+    /// write every API result to this directory, for use with --replay
+    #[structopt(long)]
+    pub record: Option<PathBuf>,
+
+    /// serve results recorded with --record from this directory instead of querying Github
+    #[structopt(long, conflicts_with = "record")]
+    pub replay: Option<PathBuf>,
+
+    /// analyze the git clones in this directory instead of querying Github
+    #[structopt(long = "local_dir", alias = "local-dir")]
+    pub local_dir: Option<PathBuf>,
+
+    /// also report the lead contributor of commits made on or after this date (YYYY-MM-DD or RFC
+    /// 3339)
+    #[structopt(long, parse(try_from_str = parse_date))]
+    since: Option<DateTime<Utc>>,
+
+    /// also report the lead contributor of commits made on or before this date (YYYY-MM-DD or RFC
+    /// 3339)
+    #[structopt(long, parse(try_from_str = parse_date))]
+    until: Option<DateTime<Utc>>,
+
+    /// also report the lead contributor of recent commits, e.g. `90d`, `6w`, `12m` or `2y`
+    #[structopt(long, parse(try_from_str = parse_period), conflicts_with_all = &["since", "until"])]
+    window: Option<chrono::Duration>,
+
+    /// weigh commits so their contribution halves every period, e.g. `180d`, instead of counting
+    /// them equally
+    #[structopt(long = "half_life", alias = "half-life", parse(try_from_str = parse_period))]
+    half_life: Option<chrono::Duration>,
+
+    /// what counts as a contribution: commits, lines (added plus deleted), reviews (of merged
+    /// pulls) or pulls (merged pulls authored)
+    #[structopt(long, possible_values = ContributionMetric::VARIANTS, conflicts_with = "half_life")]
+    metric: Option<ContributionMetric>,
+
+    /// the order to report repositories in: search (as returned by the search), name, the most
+    /// concentrated first by percentage, gini, hhi or entropy, or the riskiest first by score
+    #[structopt(
+        long = "sort_by",
+        alias = "sort-by",
+        default_value = "search",
+        possible_values = SortBy::VARIANTS
+    )]
+    sort_by: SortBy,
+
+    /// report directories where one author changed more than this share of the lines, e.g. 0.75
+    #[structopt(
+        long = "ownership_threshold",
+        alias = "ownership-threshold",
+        parse(try_from_str = parse_share)
+    )]
+    ownership_threshold: Option<f64>,
+
+    /// report paths CODEOWNERS gives to a single owner, and listed owners without recent commits
+    #[structopt(long)]
+    codeowners: bool,
+
+    /// how many of the largest contributors to list for each repo, 0 for none
+    #[structopt(long, default_value = "3")]
+    top: usize,
+
+    /// how to write the results: table, or json for every repository in full
+    #[structopt(long, default_value = "table", possible_values = OutputFormat::VARIANTS)]
+    format: OutputFormat,
+
+    /// also report the share of the lead company, from profiles or an --affiliation_file
+    #[structopt(long)]
+    affiliations: bool,
+
+    /// TOML file assigning contributors to organizations, taking precedence over profiles
+    #[structopt(long = "affiliation_file", alias = "affiliation-file")]
+    affiliation_file: Option<PathBuf>,
+
+    /// fall back to a contributor's only public Github organization when no company is set
+    #[structopt(long = "affiliation_orgs", alias = "affiliation-orgs")]
+    affiliation_orgs: bool,
+
+    /// look up when the lead of each repo with a bus factor of 1 last committed
+    #[structopt(long = "lead_activity", alias = "lead-activity")]
+    lead_activity: bool,
+
+    /// how long the lead can go without a commit before they count as dormant
+    #[structopt(
+        long = "dormant_after",
+        alias = "dormant-after",
+        default_value = "180d",
+        parse(try_from_str = parse_period)
+    )]
+    dormant_after: chrono::Duration,
+
+    /// how long the lead can go without a commit before they count as departed
+    #[structopt(
+        long = "departed_after",
+        alias = "departed-after",
+        default_value = "365d",
+        parse(try_from_str = parse_period)
+    )]
+    departed_after: chrono::Duration,
+
+    /// also look up each repo's latest release and how many pull requests await review
+    #[structopt(long)]
+    health: bool,
+
+    /// how much each part of the risk score counts, e.g. `concentration=2,archived=0`
+    #[structopt(long = "risk_weights", alias = "risk-weights", default_value = "")]
+    risk_weights: RiskWeights,
+
+    /// don't count merge commits in local repositories
+    #[structopt(long = "exclude_merges", alias = "exclude-merges")]
+    exclude_merges: bool,
+
+    /// only count commits to local repositories made on or after this date (YYYY-MM-DD or RFC
+    /// 3339)
+    #[structopt(long = "local_since", alias = "local-since", parse(try_from_str = parse_date))]
+    local_since: Option<DateTime<Utc>>,
+
+    /// only count commits to local repositories made on or before this date (YYYY-MM-DD or RFC
+    /// 3339)
+    #[structopt(long = "local_until", alias = "local-until", parse(try_from_str = parse_date))]
+    local_until: Option<DateTime<Utc>>,
+
+    /// include commits from emails not linked to a Github account
+    #[structopt(long)]
+    anonymous: bool,
+
+    /// count accounts that look like bots, e.g. `dependabot[bot]`
+    #[structopt(long = "include_bots", alias = "include-bots")]
+    include_bots: bool,
+
+    /// leave out accounts that look like bots even if the config file includes them
+    #[structopt(long = "no_include_bots", alias = "no-include-bots")]
+    no_include_bots: bool,
+
+    /// leave this login out of the calculation, can be repeated
+    #[structopt(long, number_of_values = 1)]
+    exclude: Vec<String>,
+
+    /// leave logins matching this regex out of the calculation, can be repeated
+    #[structopt(
+        long = "exclude_pattern",
+        alias = "exclude-pattern",
+        number_of_values = 1
+    )]
+    exclude_pattern: Vec<String>,
+
+    /// merge contributor identities using this .mailmap or TOML alias file
+    #[structopt(long)]
+    aliases: Option<PathBuf>,
+
+    /// directory for cached API responses [default: the platform cache directory]
+    #[structopt(long = "cache_dir", alias = "cache-dir")]
+    cache_dir: Option<PathBuf>,
+
+    /// seconds for which cached responses are used without asking the API
+    #[structopt(long = "cache_ttl", alias = "cache-ttl", default_value = "3600")]
+    cache_ttl: u64,
+
+    /// always fetch fresh data, without reading or writing the cache
+    #[structopt(long = "no_cache", alias = "no-cache")]
+    no_cache: bool,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
 }
 
-impl From<Args> for Query {
-    fn from(
-        Args {
+/// Each way of choosing the repositories to report on, besides managing the cache
+#[derive(StructOpt, Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// report on the most starred repositories in some languages
+    Search {
+        /// how many repositories to search for in each language
+        #[structopt(short = "n", long = "project_count", alias = "project-count")]
+        project_count: Option<u32>,
+
+        /// search for repositories in this language, can be repeated
+        #[structopt(short, long, number_of_values = 1)]
+        language: Vec<String>,
+    },
+    /// report on repositories given as `owner/name`
+    Repo {
+        #[structopt(required = true)]
+        repos: Vec<Repository>,
+    },
+    /// report on the Github repositories of a Cargo.toml's dependencies
+    Deps {
+        #[structopt(default_value = "Cargo.toml")]
+        manifest: PathBuf,
+
+        /// crates.io compatible API used to find each crate's repository
+        #[structopt(
+            long = "registry_url",
+            alias = "registry-url",
+            default_value = DEFAULT_REGISTRY_URL
+        )]
+        registry_url: String,
+    },
+    /// report on the dependencies a new version of a Cargo.toml adds, e.g. in a pull request
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+
+        /// crates.io compatible API used to find each crate's repository
+        #[structopt(
+            long = "registry_url",
+            alias = "registry-url",
+            default_value = DEFAULT_REGISTRY_URL
+        )]
+        registry_url: String,
+    },
+    /// manage cached API responses
+    Cache(CacheCommand),
+    /// answer `GET /repos/<owner>/<name>` with the JSON report for that repository
+    Serve {
+        #[structopt(long, default_value = "127.0.0.1:8080")]
+        address: SocketAddr,
+    },
+}
+
+#[derive(StructOpt, Debug, Clone, PartialEq, Eq)]
+pub enum CacheCommand {
+    /// delete every cached response
+    Clear,
+}
+
+impl Args {
+    /// parse the command line over any config files, exiting on invalid arguments like
+    /// [`StructOpt::from_args`]
+    pub fn load() -> Result<Self, Error> {
+        let argv: Vec<OsString> = std::env::args_os().collect();
+        let mut config = Config::discover(config_path(&argv).as_deref())?;
+        // the environment is more specific to this run than a config file
+        if std::env::var_os(API_URL_ENV).is_some() {
+            config.base_url = None;
+        }
+        Ok(Self::from_iter_with_config(argv, &config).unwrap_or_else(|e| e.exit()))
+    }
+
+    /// parse `iter` as the command line, with the config's values as defaults
+    pub fn from_iter_with_config<I>(iter: I, config: &Config) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator,
+        I::Item: Into<OsString>,
+    {
+        let mut argv: Vec<OsString> = iter.into_iter().map(Into::into).collect();
+        let defaults = config.to_args();
+        let first_explicit = defaults.len() + 1;
+        let explicit = argv.split_off(argv.len().min(1));
+        argv.extend(defaults);
+        argv.extend(explicit);
+
+        let matches = Self::clap()
+            .get_matches_from_safe(argv)
+            .map_err(suggest_flag)?;
+        let mut args = Self::from_clap(&matches);
+
+        // lists given on the command line replace the config's, rather than adding to it; clap
+        // knows the args by their field names in kebab case
+        let from_config = |name: &str| -> Option<usize> {
+            let indices: Vec<_> = matches.indices_of(name)?.collect();
+            let count = indices.iter().filter(|&&i| i < first_explicit).count();
+            (count < indices.len()).then_some(count)
+        };
+        if let Some(count) = from_config("language") {
+            args.language.drain(..count);
+        }
+        if let Some(count) = from_config("repo") {
+            args.repo.drain(..count);
+        }
+        if let Some(count) = from_config("exclude") {
+            args.exclude.drain(..count);
+        }
+        if let Some(count) = from_config("exclude-pattern") {
+            args.exclude_pattern.drain(..count);
+        }
+        // flags are left out of the config's args, so the config only applies when neither flag
+        // is given, otherwise the later one wins
+        let last = |name| matches.indices_of(name).and_then(Iterator::max);
+        args.include_bots = match (last("include-bots"), last("no-include-bots")) {
+            (None, None) => config.include_bots.unwrap_or_default(),
+            (include, exclude) => include > exclude,
+        };
+
+        // `search` falls back to the top level options, and so to the config
+        if let Some(Command::Search {
             project_count,
             language,
-            key_file: _,
-        }: Args,
-    ) -> Self {
-        Self {
-            limit: project_count,
-            language,
+        }) = &mut args.command
+        {
+            *project_count = project_count.or(args.project_count);
+            if language.is_empty() {
+                language.clone_from(&args.language);
+            }
+            if project_count.is_none() || language.is_empty() {
+                return Err(clap::Error::with_description(
+                    "search needs --project_count and at least one --language",
+                    clap::ErrorKind::MissingRequiredArgument,
+                ));
+            }
+        }
+        Ok(args)
+    }
+
+    /// a search for each language
+    pub fn queries(&self) -> Vec<Query> {
+        let (project_count, languages) = match &self.command {
+            None => (self.project_count, &self.language),
+            Some(Command::Search {
+                project_count,
+                language,
+            }) => (*project_count, language),
+            Some(_) => return vec![],
+        };
+        let Some(limit) = project_count else {
+            return vec![];
+        };
+        languages
+            .iter()
+            .map(|language| Query {
+                limit,
+                language: language.clone(),
+            })
+            .collect()
+    }
+
+    /// the repositories named with `--repo` or the `repo` subcommand
+    pub fn repositories(&self) -> Vec<Repository> {
+        match &self.command {
+            None => self.repo.clone(),
+            Some(Command::Repo { repos }) => repos.clone(),
+            Some(_) => vec![],
+        }
+    }
+
+    /// the repositories named on the command line, or those of the dependencies picked by `deps`
+    /// or `diff`, which are looked up in the registry
+    pub async fn named_repositories(&self) -> Result<Vec<Repository>, Error> {
+        let (dependencies, registry_url) = match &self.command {
+            Some(Command::Deps {
+                manifest,
+                registry_url,
+            }) => (deps::load_manifest(manifest)?, registry_url),
+            Some(Command::Diff {
+                old,
+                new,
+                registry_url,
+            }) => {
+                let (old, new) = (deps::load_manifest(old)?, deps::load_manifest(new)?);
+                (deps::added(&old, &new), registry_url)
+            }
+            _ => return Ok(self.repositories()),
+        };
+        info!(
+            "looking up the repositories of {} dependencies",
+            dependencies.len()
+        );
+        let registry = Registry::new(registry_url, &self.user_agent);
+        registry.repositories(&dependencies).await
+    }
+
+    /// the on-disk cache, or `None` if caching is disabled
+    pub fn disk_cache(&self) -> Option<DiskCache> {
+        if self.no_cache {
+            return None;
+        }
+        let dir = self.cache_dir.clone().or_else(DiskCache::default_dir)?;
+        Some(DiskCache::new(dir))
+    }
+
+    pub fn cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.cache_ttl)
+    }
+
+    pub fn auth_options(&self) -> AuthOptions {
+        AuthOptions {
+            key_file: self.key_file.clone(),
+            base_url: self.base_url.clone(),
+            source: self.auth_source,
+        }
+    }
+
+    pub fn client_builder(&self, api_key: ApiKey) -> DefaultClientBuilder {
+        let retry = RetryPolicy {
+            max_retries: self.retries,
+            ..Default::default()
+        };
+        let mut builder = DefaultClient::builder(api_key)
+            .base_url(&self.base_url)
+            .user_agent(&self.user_agent)
+            .retry_policy(retry)
+            .anonymous_contributors(self.anonymous);
+        if let Some(max) = self.concurrency {
+            builder = builder.max_concurrency(max);
+        }
+        if let Some(secs) = self.connect_timeout {
+            builder = builder.connect_timeout(Duration::from_secs(secs));
+        }
+        if let Some(secs) = self.timeout {
+            builder = builder.timeout(Duration::from_secs(secs));
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy);
+        }
+        if let Some(path) = &self.ca_bundle {
+            builder = builder.ca_bundle(path);
+        }
+        builder
+    }
+
+    pub fn analysis_options(&self) -> Result<AnalysisOptions, Error> {
+        let mut bot_filter = BotFilter::default().detect_bots(!self.include_bots);
+        for login in &self.exclude {
+            bot_filter = bot_filter.deny(login);
+        }
+        for pattern in &self.exclude_pattern {
+            bot_filter = bot_filter.pattern(pattern)?;
+        }
+        let identities = match &self.aliases {
+            Some(path) => Identities::load(path)?,
+            None => Identities::default(),
+        };
+        let affiliations = match &self.affiliation_file {
+            Some(path) => Some(Affiliations::load(path)?),
+            None if self.affiliations || self.affiliation_orgs => Some(Affiliations::default()),
+            None => None,
+        };
+        if self.departed_after < self.dormant_after {
+            return Err("--departed_after must not be shorter than --dormant_after".into());
+        }
+        Ok(AnalysisOptions {
+            bot_filter,
+            identities,
+            window: self.window(),
+            weighting: match self.half_life {
+                Some(half_life) => Weighting::Decay { half_life },
+                None => Weighting::Count,
+            },
+            metric: self.metric.unwrap_or_default(),
+            sort_by: self.sort_by,
+            ownership: self
+                .ownership_threshold
+                .map(|threshold| OwnershipOptions { threshold }),
+            code_owners: self.codeowners,
+            top: self.top,
+            format: self.format,
+            affiliations: affiliations
+                .map(|affiliations| affiliations.use_organizations(self.affiliation_orgs)),
+            activity: self.lead_activity.then_some(ActivityOptions {
+                dormant_after: self.dormant_after,
+                departed_after: self.departed_after,
+            }),
+            project_health: self.health,
+            risk_weights: self.risk_weights,
+            threshold: self.threshold,
+        })
+    }
+
+    /// the time window for recent activity, if one was asked for
+    pub fn window(&self) -> Option<Window> {
+        match self.window {
+            // counted from the start of today, so repeated runs share cache entries
+            Some(duration) => {
+                let today = Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap();
+                Some(Window {
+                    since: Some(today.and_utc() - duration),
+                    until: None,
+                })
+            }
+            None if self.since.is_some() || self.until.is_some() => Some(Window {
+                since: self.since,
+                until: self.until,
+            }),
+            None => None,
+        }
+    }
+
+    pub fn local_options(&self) -> LocalGitOptions {
+        LocalGitOptions {
+            since: self.local_since,
+            until: self.local_until,
+            exclude_merges: self.exclude_merges,
         }
     }
 }
 
+fn parse_date(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap().and_utc());
+    }
+    DateTime::parse_from_rfc3339(s)
+        .map(|date| date.with_timezone(&Utc))
+        .map_err(|e| format!("invalid date {:?}: {}", s, e))
+}
+
+/// a fraction between 0 and 1
+fn parse_share(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(share) if (0.0..=1.0).contains(&share) => Ok(share),
+        _ => Err(format!(
+            "invalid share {:?}, expected a number between 0 and 1",
+            s
+        )),
+    }
+}
+
+/// a number of days, weeks, months (30 days) or years (365 days)
+fn parse_period(s: &str) -> Result<chrono::Duration, String> {
+    let invalid = || {
+        format!(
+            "invalid period {:?}, expected e.g. `90d`, `6w`, `12m` or `2y`",
+            s
+        )
+    };
+    let split = s
+        .len()
+        .checked_sub(1)
+        .filter(|&i| s.is_char_boundary(i))
+        .ok_or_else(invalid)?;
+    let (count, unit) = s.split_at(split);
+    let count: i64 = count.parse().map_err(|_| invalid())?;
+    let days = match unit {
+        "d" => 1,
+        "w" => 7,
+        "m" => 30,
+        "y" => 365,
+        _ => return Err(invalid()),
+    };
+    Ok(chrono::Duration::days(count * days))
+}
+
+/// the query for the first language, see [`Args::queries`] for all of them
+/// the first search, for callers that only run one
+impl TryFrom<Args> for Query {
+    type Error = Error;
+
+    fn try_from(args: Args) -> Result<Self, Error> {
+        match args.queries().into_iter().next() {
+            Some(query) => Ok(query),
+            None => bail!("no search, missing --project_count or --language"),
+        }
+    }
+}
+
+/// every search, so that running only `--repo` or a subcommand searches for nothing
+impl IntoQueries for Args {
+    fn into_queries(self) -> Vec<Query> {
+        self.queries()
+    }
+}
+
+/// the value of `--config`, found before parsing since it decides what else is parsed
+fn config_path(argv: &[OsString]) -> Option<PathBuf> {
+    let mut argv = argv.iter().skip(1);
+    while let Some(arg) = argv.next() {
+        let arg = arg.to_string_lossy();
+        if arg == "--config" {
+            return argv.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg.strip_prefix("--config=") {
+            return Some(path.into());
+        }
+    }
+    None
+}
+
+/// clap only suggests flags spelled almost the same as an unknown one, and only those of the
+/// subcommand being parsed, so also suggest the shared option with the closest name
+fn suggest_flag(mut error: clap::Error) -> clap::Error {
+    if error.kind != clap::ErrorKind::UnknownArgument || error.message.contains("Did you mean") {
+        return error;
+    }
+    let Some(unknown) = error.info.as_ref().and_then(|info| info.first()) else {
+        return error;
+    };
+    let Some(unknown) = unknown.strip_prefix("--") else {
+        return error;
+    };
+    let unknown = unknown
+        .split('=')
+        .next()
+        .unwrap_or_default()
+        .replace('-', "_");
+
+    let mut help = vec![];
+    if Args::clap().write_help(&mut help).is_err() {
+        return error;
+    }
+    let flags = regex::Regex::new(r"(?m)^\s+(?:-\w, )?--(\w+)").expect("valid regex");
+    let help = String::from_utf8_lossy(&help);
+    let closest = flags
+        .captures_iter(&help)
+        .map(|captures| captures[1].to_string())
+        .map(|flag| (edit_distance(&unknown, &flag), flag))
+        .filter(|(distance, flag)| {
+            *distance <= 2 || (unknown.len() > 2 && flag.contains(unknown.as_str()))
+        })
+        .min();
+
+    let hint = match closest {
+        Some((0, flag)) => {
+            format!(
+                "\n\t--{} is a shared option, give it before the subcommand",
+                flag
+            )
+        }
+        Some((_, flag)) => format!("\n\tDid you mean --{}?", flag),
+        None => return error,
+    };
+    if let Some(end) = error.message.find('\n') {
+        error.message.insert_str(end, &hint);
+    }
+    error
+}
+
+/// the number of single character insertions, deletions or substitutions between the two
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
 #[cfg(test)]
 mod tests {
     use std::ffi::OsString;
@@ -43,9 +761,55 @@ mod tests {
         assert_eq!(
             Args::from_iter(["bus-factor", "--project_count", "10", "--language", "rust"]),
             Args {
-                project_count: 10,
-                language: "rust".to_string(),
+                project_count: Some(10),
+                language: vec!["rust".to_string()],
+                repo: vec![],
+                config: None,
+                threshold: None,
                 key_file: None,
+                auth_source: None,
+                base_url: DEFAULT_BASE_URL.into(),
+                user_agent: DEFAULT_USER_AGENT.into(),
+                retries: 3,
+                concurrency: None,
+                connect_timeout: None,
+                timeout: None,
+                proxy: None,
+                ca_bundle: None,
+                record: None,
+                replay: None,
+                local_dir: None,
+                since: None,
+                until: None,
+                window: None,
+                half_life: None,
+                metric: None,
+                sort_by: SortBy::Search,
+                ownership_threshold: None,
+                codeowners: false,
+                top: 3,
+                format: OutputFormat::Table,
+                affiliations: false,
+                affiliation_file: None,
+                affiliation_orgs: false,
+                lead_activity: false,
+                dormant_after: chrono::Duration::days(180),
+                departed_after: chrono::Duration::days(365),
+                health: false,
+                risk_weights: RiskWeights::default(),
+                exclude_merges: false,
+                local_since: None,
+                local_until: None,
+                anonymous: false,
+                include_bots: false,
+                no_include_bots: false,
+                exclude: vec![],
+                exclude_pattern: vec![],
+                aliases: None,
+                cache_dir: None,
+                cache_ttl: 3600,
+                no_cache: false,
+                command: None,
             }
         );
     }
@@ -55,15 +819,582 @@ mod tests {
         assert_eq!(
             Args::from_iter(["bus-factor", "--project_count", "10", "--language", "rust", "--key_file", "/path/to/file"]),
             Args {
-                project_count: 10,
-                language: "rust".to_string(),
+                project_count: Some(10),
+                language: vec!["rust".to_string()],
+                repo: vec![],
+                config: None,
+                threshold: None,
                 key_file: Some("/path/to/file".into()),
+                auth_source: None,
+                base_url: DEFAULT_BASE_URL.into(),
+                user_agent: DEFAULT_USER_AGENT.into(),
+                retries: 3,
+                concurrency: None,
+                connect_timeout: None,
+                timeout: None,
+                proxy: None,
+                ca_bundle: None,
+                record: None,
+                replay: None,
+                local_dir: None,
+                since: None,
+                until: None,
+                window: None,
+                half_life: None,
+                metric: None,
+                sort_by: SortBy::Search,
+                ownership_threshold: None,
+                codeowners: false,
+                top: 3,
+                format: OutputFormat::Table,
+                affiliations: false,
+                affiliation_file: None,
+                affiliation_orgs: false,
+                lead_activity: false,
+                dormant_after: chrono::Duration::days(180),
+                departed_after: chrono::Duration::days(365),
+                health: false,
+                risk_weights: RiskWeights::default(),
+                exclude_merges: false,
+                local_since: None,
+                local_until: None,
+                anonymous: false,
+                include_bots: false,
+                no_include_bots: false,
+                exclude: vec![],
+                exclude_pattern: vec![],
+                aliases: None,
+                cache_dir: None,
+                cache_ttl: 3600,
+                no_cache: false,
+                command: None,
+            }
+        );
+    }
+
+    #[test]
+    fn base_url() {
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--base_url",
+            "https://ghe.corp/api/v3",
+        ]);
+        assert_eq!(args.base_url, "https://ghe.corp/api/v3");
+    }
+
+    #[test]
+    fn auth_options() {
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--base_url",
+            "https://ghe.corp/api/v3",
+            "--auth-source",
+            "netrc",
+        ]);
+        let options = args.auth_options();
+        assert_eq!(options.source, Some(AuthSource::Netrc));
+        assert_eq!(options.base_url, "https://ghe.corp/api/v3");
+
+        assert_fails_parse([
+            "bus-factor",
+            "-n",
+            "10",
+            "-l",
+            "rust",
+            "--auth_source",
+            "vault",
+        ]);
+    }
+
+    #[test]
+    fn local_options() {
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--local_dir",
+            "/path/to/clones",
+            "--since",
+            "2021-01-01",
+            "--until",
+            "2021-06-01T12:00:00+02:00",
+            "--local_since",
+            "2020-01-01",
+            "--local-until",
+            "2020-12-31",
+            "--exclude_merges",
+        ]);
+        assert_eq!(args.local_dir, Some("/path/to/clones".into()));
+        assert_eq!(
+            args.local_options(),
+            LocalGitOptions {
+                since: Some("2020-01-01T00:00:00Z".parse().unwrap()),
+                until: Some("2020-12-31T00:00:00Z".parse().unwrap()),
+                exclude_merges: true,
+            }
+        );
+        // --since and --until are the recent window, with local clones as with the API
+        assert_eq!(
+            args.window(),
+            Some(Window {
+                since: Some("2021-01-01T00:00:00Z".parse().unwrap()),
+                until: Some("2021-06-01T10:00:00Z".parse().unwrap()),
+            })
+        );
+    }
+
+    #[test]
+    fn window_options() {
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--window",
+            "12m",
+        ]);
+        let since = args.window().unwrap().since.unwrap();
+        assert_eq!(
+            since,
+            since.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc()
+        );
+        assert!(Utc::now() - since >= chrono::Duration::days(360));
+
+        assert_eq!(parse_period("90d"), Ok(chrono::Duration::days(90)));
+        assert_eq!(parse_period("2y"), Ok(chrono::Duration::days(730)));
+        assert!(parse_period("12").is_err());
+        assert!(parse_period("m").is_err());
+        assert!(parse_period("").is_err());
+    }
+
+    #[test]
+    fn half_life_option() {
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--half_life",
+            "26w",
+        ]);
+        assert_eq!(
+            args.analysis_options().unwrap().weighting,
+            Weighting::Decay {
+                half_life: chrono::Duration::days(182)
             }
         );
     }
 
+    #[test]
+    fn metric_option() {
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--metric",
+            "reviews",
+        ]);
+        assert_eq!(
+            args.analysis_options().unwrap().metric,
+            ContributionMetric::Reviews
+        );
+
+        assert_fails_parse([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--metric",
+            "stars",
+        ]);
+    }
+
+    #[test]
+    fn sort_by_option() {
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--sort_by",
+            "gini",
+        ]);
+        assert_eq!(args.analysis_options().unwrap().sort_by, SortBy::Gini);
+    }
+
+    #[test]
+    fn ownership_option() {
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--ownership_threshold",
+            "0.8",
+        ]);
+        assert_eq!(
+            args.analysis_options().unwrap().ownership,
+            Some(OwnershipOptions { threshold: 0.8 })
+        );
+
+        assert_fails_parse([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--ownership_threshold",
+            "80",
+        ]);
+    }
+
+    #[test]
+    fn output_options() {
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--top",
+            "10",
+            "--format",
+            "json",
+        ]);
+        let options = args.analysis_options().unwrap();
+        assert_eq!(options.top, 10);
+        assert_eq!(options.format, OutputFormat::Json);
+        assert!(!options.project_health);
+
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--health",
+        ]);
+        assert!(args.analysis_options().unwrap().project_health);
+    }
+
+    #[test]
+    fn risk_options() {
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--sort_by",
+            "score",
+            "--risk_weights",
+            "archived=0",
+        ]);
+        let options = args.analysis_options().unwrap();
+        assert_eq!(options.sort_by, SortBy::Score);
+        assert_eq!(options.risk_weights.archived, 0.0);
+
+        let result = Args::from_iter_safe([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--risk_weights",
+            "stars=1",
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn affiliation_options() {
+        let args = Args::from_iter(["bus-factor", "--project_count", "10", "--language", "rust"]);
+        assert!(args.analysis_options().unwrap().affiliations.is_none());
+
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--affiliation_orgs",
+        ]);
+        assert!(args.analysis_options().unwrap().affiliations.is_some());
+
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--affiliation_file",
+            "/does/not/exist.toml",
+        ]);
+        assert!(args.analysis_options().is_err());
+    }
+
+    #[test]
+    fn lead_activity_options() {
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--lead_activity",
+        ]);
+        let activity = args.analysis_options().unwrap().activity.unwrap();
+        assert_eq!(activity, ActivityOptions::default());
+
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--lead_activity",
+            "--dormant_after",
+            "90d",
+        ]);
+        let activity = args.analysis_options().unwrap().activity.unwrap();
+        assert_eq!(activity.dormant_after, chrono::Duration::days(90));
+        assert_eq!(activity.departed_after, chrono::Duration::days(365));
+
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--lead_activity",
+            "--departed_after",
+            "1m",
+        ]);
+        assert!(args.analysis_options().is_err());
+    }
+
+    #[test]
+    fn window_conflicts_with_since() {
+        assert_fails_parse([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--window",
+            "12m",
+            "--since",
+            "2021-01-01",
+        ]);
+    }
+
+    #[test]
+    fn bot_filter_options() {
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--exclude",
+            "bors",
+            "--exclude",
+            "homu",
+            "--exclude_pattern",
+            "^ci-",
+        ]);
+        assert_eq!(args.exclude, vec!["bors", "homu"]);
+        assert_eq!(args.exclude_pattern, vec!["^ci-"]);
+        assert!(args.analysis_options().is_ok());
+
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--exclude_pattern",
+            "(",
+        ]);
+        assert!(args.analysis_options().is_err());
+    }
+
+    #[test]
+    fn cache_options() {
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--cache_dir",
+            "/path/to/cache",
+            "--cache_ttl",
+            "60",
+        ]);
+        assert!(args.disk_cache().is_some());
+        assert_eq!(args.cache_ttl(), Duration::from_secs(60));
+
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--no_cache",
+        ]);
+        assert!(args.disk_cache().is_none());
+    }
+
+    #[test]
+    fn cache_clear_needs_no_query() {
+        let args = Args::from_iter(["bus-factor", "cache", "clear"]);
+        assert_eq!(args.command, Some(Command::Cache(CacheCommand::Clear)));
+    }
+
+    #[test]
+    fn subcommands_choose_repositories() {
+        let args = Args::from_iter([
+            "bus-factor",
+            "--format",
+            "json",
+            "search",
+            "--project_count",
+            "5",
+            "--language",
+            "go",
+        ]);
+        assert_eq!(args.format, OutputFormat::Json);
+        assert_eq!(
+            args.queries(),
+            [Query {
+                language: "go".into(),
+                limit: 5,
+            }]
+        );
+        assert_fails_parse(["bus-factor", "search", "--language", "go"]);
+
+        let args = Args::from_iter(["bus-factor", "repo", "rust-lang/rust", "tokio-rs/tokio"]);
+        assert!(args.queries().is_empty());
+        assert_eq!(args.repositories().len(), 2);
+        assert_fails_parse(["bus-factor", "repo"]);
+        assert_fails_parse(["bus-factor", "repo", "rust"]);
+
+        let args = Args::from_iter(["bus-factor", "serve", "--address", "0.0.0.0:80"]);
+        assert_eq!(
+            args.command,
+            Some(Command::Serve {
+                address: "0.0.0.0:80".parse().unwrap()
+            })
+        );
+    }
+
+    #[test]
+    fn subcommands_ignore_the_old_flags() {
+        let config = Config {
+            project_count: Some(20),
+            languages: vec!["rust".into()],
+            repos: vec!["rust-lang/rust".into()],
+            ..Default::default()
+        };
+        let args = Args::from_iter_with_config(["bus-factor", "repo", "owner/name"], &config);
+        let args = args.unwrap();
+        assert!(args.queries().is_empty());
+        assert_eq!(args.repositories(), ["owner/name".parse().unwrap()]);
+
+        // but `search` falls back to them
+        let args = Args::from_iter_with_config(["bus-factor", "search"], &config).unwrap();
+        assert_eq!(args.queries()[0].limit, 20);
+        assert!(args.repositories().is_empty());
+        let argv = ["bus-factor", "search", "--language", "go"];
+        let args = Args::from_iter_with_config(argv, &config).unwrap();
+        assert_eq!(args.queries()[0].language, "go");
+    }
+
+    #[tokio::test]
+    async fn deps_and_diff_read_manifests() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old.toml");
+        let new = dir.path().join("new.toml");
+        std::fs::write(&old, "[dependencies]\nserde = \"1\"\n").unwrap();
+        std::fs::write(
+            &new,
+            "[dependencies]\nserde = \"1\"\nfork = { git = \"https://github.com/o/fork\" }\n",
+        )
+        .unwrap();
+
+        let argv = [
+            OsString::from("bus-factor"),
+            "diff".into(),
+            old.into(),
+            new.clone().into(),
+        ];
+        let args = Args::from_iter(argv);
+        assert_eq!(
+            args.named_repositories().await.unwrap(),
+            ["o/fork".parse().unwrap()]
+        );
+
+        let (url, requests) = crate::tests::serve(vec![crate::tests::MockResponse {
+            status: 200,
+            headers: vec![("Content-Type", "application/json")],
+            body: r#"{"crate": {"repository": "https://github.com/serde-rs/serde"}}"#,
+        }])
+        .await;
+        let argv = [
+            OsString::from("bus-factor"),
+            "deps".into(),
+            new.into(),
+            "--registry_url".into(),
+            url.into(),
+        ];
+        let args = Args::from_iter(argv);
+        let names: Vec<_> = args
+            .named_repositories()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|repo| format!("{}/{}", repo.owner.login, repo.name))
+            .collect();
+        assert_eq!(names, ["o/fork", "serde-rs/serde"]);
+        assert_eq!(requests.lock().unwrap().len(), 1);
+
+        let args = Args::from_iter(["bus-factor", "deps", "/does/not/Cargo.toml"]);
+        assert!(args.named_repositories().await.is_err());
+    }
+
+    #[test]
+    fn invalid_date() {
+        assert_fails_parse([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--since",
+            "last tuesday",
+        ]);
+    }
+
     fn assert_fails_parse<T: IntoIterator<Item = I>, I: Into<OsString> + Clone>(t: T) {
-        let result = Args::from_iter_safe(t);
+        let result = Args::from_iter_with_config(t, &Config::default());
         assert!(result.is_err());
     }
 
@@ -84,15 +1415,249 @@ mod tests {
             "not-sure",
         ]);
     }
+
+    #[test]
+    fn accepts_both_spellings() {
+        let parse = |argv: &[&str]| Args::from_iter_with_config(argv, &Config::default()).unwrap();
+        let snake = parse(&[
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--key_file",
+            "/path/to/file",
+            "--sort_by",
+            "gini",
+            "--no_cache",
+        ]);
+        let kebab = parse(&[
+            "bus-factor",
+            "--project-count",
+            "10",
+            "--language",
+            "rust",
+            "--key-file",
+            "/path/to/file",
+            "--sort-by=gini",
+            "--no-cache",
+        ]);
+        let short = parse(&[
+            "bus-factor",
+            "-n",
+            "10",
+            "-l",
+            "rust",
+            "--key_file",
+            "/path/to/file",
+            "--sort-by",
+            "gini",
+            "--no_cache",
+        ]);
+        assert_eq!(snake, kebab);
+        assert_eq!(snake, short);
+
+        let search = parse(&["bus-factor", "search", "-n", "5", "-l", "go"]);
+        assert_eq!(
+            search,
+            parse(&["bus-factor", "search", "--project-count", "5", "-l", "go"])
+        );
+        let deps = parse(&["bus-factor", "deps", "--registry-url", "http://registry"]);
+        assert!(
+            matches!(deps.command, Some(Command::Deps { registry_url, .. })
+            if registry_url == "http://registry")
+        );
+    }
+
+    #[test]
+    fn suggests_closest_flag() {
+        let message = |argv: &[&str]| {
+            Args::from_iter_with_config(argv, &Config::default())
+                .unwrap_err()
+                .message
+        };
+        let count = message(&["bus-factor", "--count", "10", "--language", "rust"]);
+        assert!(count.contains("Did you mean --project_count?"), "{}", count);
+
+        let misplaced = message(&["bus-factor", "repo", "o/n", "--sort-by", "gini"]);
+        assert!(
+            misplaced.contains("--sort_by is a shared option"),
+            "{}",
+            misplaced
+        );
+
+        let unknown = message(&["bus-factor", "-n", "10", "-l", "rust", "--whats-this", "x"]);
+        assert!(!unknown.contains("Did you mean"), "{}", unknown);
+
+        assert_eq!(edit_distance("sort_by", "sort_by"), 0);
+        assert_eq!(edit_distance("langauge", "language"), 2);
+        assert_eq!(edit_distance("", "top"), 3);
+    }
+
     #[test]
     fn missing_option() {
         assert_fails_parse(["bus-factor", "--project-count", "10"]);
     }
 
+    #[test]
+    fn repeats_languages_and_names_repositories() {
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--language",
+            "go",
+            "--repo",
+            "rust-lang/rust",
+        ]);
+        let languages: Vec<_> = args.queries().into_iter().map(|q| q.language).collect();
+        assert_eq!(languages, ["rust", "go"]);
+        assert_eq!(args.repositories(), vec!["rust-lang/rust".parse().unwrap()]);
+
+        let args = Args::from_iter(["bus-factor", "--repo", "rust-lang/rust"]);
+        assert!(args.queries().is_empty());
+        assert_fails_parse(["bus-factor", "--repo", "rust"]);
+    }
+
+    #[test]
+    fn threshold_option() {
+        let args = Args::from_iter([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--threshold",
+            "0.5",
+        ]);
+        assert_eq!(args.analysis_options().unwrap().threshold, Some(0.5));
+        assert_fails_parse([
+            "bus-factor",
+            "--project_count",
+            "10",
+            "--language",
+            "rust",
+            "--threshold",
+            "2",
+        ]);
+    }
+
+    #[test]
+    fn config_supplies_required_options() {
+        let config = Config {
+            project_count: Some(20),
+            languages: vec!["rust".into(), "go".into()],
+            format: Some("json".into()),
+            include_bots: Some(true),
+            ..Default::default()
+        };
+        let args = Args::from_iter_with_config(["bus-factor"], &config).unwrap();
+        assert_eq!(args.queries().len(), 2);
+        assert_eq!(args.queries()[0].limit, 20);
+        let options = args.analysis_options().unwrap();
+        assert_eq!(options.format, OutputFormat::Json);
+        assert!(args.include_bots);
+
+        assert!(Args::from_iter_with_config(["bus-factor"], &Config::default()).is_err());
+    }
+
+    #[test]
+    fn command_line_overrides_config() {
+        let config = Config {
+            project_count: Some(20),
+            languages: vec!["rust".into(), "go".into()],
+            format: Some("json".into()),
+            exclude: vec!["ci-user".into()],
+            exclude_pattern: vec!["^ci-".into()],
+            cache_dir: Some("/from/config".into()),
+            ..Default::default()
+        };
+        let args = Args::from_iter_with_config(
+            [
+                "bus-factor",
+                "--language",
+                "python",
+                "--format=table",
+                "--cache_dir",
+                "/from/args",
+                "--exclude_pattern",
+                "^release-",
+            ],
+            &config,
+        )
+        .unwrap();
+        let languages: Vec<_> = args.queries().into_iter().map(|q| q.language).collect();
+        assert_eq!(languages, ["python"]);
+        assert_eq!(args.queries()[0].limit, 20);
+        assert_eq!(args.format, OutputFormat::Table);
+        assert_eq!(args.exclude, ["ci-user"]);
+        assert_eq!(args.exclude_pattern, ["^release-"]);
+        assert_eq!(args.cache_dir, Some("/from/args".into()));
+    }
+
+    #[test]
+    fn command_line_overrides_config_bots() {
+        let config = Config {
+            project_count: Some(20),
+            languages: vec!["rust".into()],
+            include_bots: Some(true),
+            ..Default::default()
+        };
+        let include_bots = |argv: &[&str]| {
+            let argv = ["bus-factor"].iter().chain(argv);
+            Args::from_iter_with_config(argv, &config)
+                .unwrap()
+                .include_bots
+        };
+        assert!(include_bots(&[]));
+        assert!(!include_bots(&["--no_include_bots"]));
+        assert!(!include_bots(&["--no-include-bots"]));
+        assert!(include_bots(&["--no_include_bots", "--include_bots"]));
+        assert!(!include_bots(&["--include_bots", "--no_include_bots"]));
+    }
+
+    #[test]
+    fn invalid_config_values_are_reported() {
+        let config = Config {
+            format: Some("xml".into()),
+            ..Default::default()
+        };
+        let result = Args::from_iter_with_config(
+            ["bus-factor", "--project_count", "10", "--language", "rust"],
+            &config,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn finds_config_path() {
+        let argv = |args: &[&str]| -> Vec<OsString> { args.iter().map(Into::into).collect() };
+        assert_eq!(
+            config_path(&argv(&[
+                "bus-factor",
+                "--config",
+                "ci.toml",
+                "--language",
+                "rust"
+            ])),
+            Some("ci.toml".into())
+        );
+        assert_eq!(
+            config_path(&argv(&["bus-factor", "--config=ci.toml"])),
+            Some("ci.toml".into())
+        );
+        assert_eq!(
+            config_path(&argv(&["bus-factor", "--language", "rust"])),
+            None
+        );
+    }
+
     #[test]
     fn can_convert_to_query() {
         let args = Args::from_iter(["bus-factor", "--project_count", "10", "--language", "rust"]);
-        let query: Query = args.into();
+        let query = Query::try_from(args.clone()).unwrap();
         assert_eq!(
             query,
             Query {
@@ -100,5 +1665,10 @@ mod tests {
                 limit: 10,
             }
         );
+        assert_eq!(args.into_queries(), vec![query]);
+
+        let args = Args::from_iter(["bus-factor", "--repo", "rust-lang/rust"]);
+        assert!(Query::try_from(args.clone()).is_err());
+        assert!(args.into_queries().is_empty());
     }
 }
@@ -0,0 +1,476 @@
+use std::collections::HashMap;
+use std::fs;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{client::GithubClient, errors::Error, model::*};
+
+/// A previously fetched response body, with the validators needed to revalidate it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub stored_at: DateTime<Utc>,
+    pub body: String,
+}
+
+impl CachedResponse {
+    fn is_fresh(&self, ttl: Duration) -> bool {
+        let age = Utc::now().signed_duration_since(self.stored_at);
+        age.to_std().map_or(true, |age| age < ttl) // a negative age means the clock moved backwards
+    }
+}
+
+/// Storage for API responses, keyed by the full request URL
+///
+/// When a cached response has an ETag or Last-Modified value, [`DefaultClient`] sends a conditional
+/// request, and reuses the cached body if the API answers with `304 Not Modified`
+///
+/// [`DefaultClient`]: crate::client::DefaultClient
+pub trait ResponseCache: Send + Sync {
+    fn get(&self, url: &str) -> Option<CachedResponse>;
+    fn put(&self, url: &str, response: CachedResponse);
+}
+
+/// Keeps responses for the lifetime of the process
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    entries: Mutex<HashMap<String, CachedResponse>>,
+}
+
+impl ResponseCache for MemoryCache {
+    fn get(&self, url: &str) -> Option<CachedResponse> {
+        self.entries.lock().unwrap().get(url).cloned()
+    }
+
+    fn put(&self, url: &str, response: CachedResponse) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(url.to_owned(), response);
+    }
+}
+
+/// Keeps responses as one JSON file per URL, so they survive between runs
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DiskEntry {
+    url: String,
+    #[serde(flatten)]
+    response: CachedResponse,
+}
+
+impl DiskCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// `$XDG_CACHE_HOME/bus-factor`, or the platform equivalent
+    pub fn default_dir() -> Option<PathBuf> {
+        dirs::cache_dir().map(|dir| dir.join("bus-factor"))
+    }
+
+    /// remove every cached response
+    pub fn clear(&self) -> Result<(), Error> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn path(&self, url: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", fnv1a(url)))
+    }
+
+    fn read(&self, url: &str) -> Option<CachedResponse> {
+        let contents = fs::read_to_string(self.path(url)).ok()?;
+        let entry: DiskEntry = serde_json::from_str(&contents).ok()?;
+        (entry.url == url).then_some(entry.response) // guard against hash collisions
+    }
+
+    fn write(&self, url: &str, response: CachedResponse) -> Result<(), Error> {
+        fs::create_dir_all(&self.dir)?;
+        let entry = DiskEntry {
+            url: url.to_owned(),
+            response,
+        };
+        fs::write(self.path(url), serde_json::to_string(&entry)?)?;
+        Ok(())
+    }
+}
+
+impl ResponseCache for DiskCache {
+    fn get(&self, url: &str) -> Option<CachedResponse> {
+        self.read(url)
+    }
+
+    fn put(&self, url: &str, response: CachedResponse) {
+        // a cache that can't be written is slower, not broken
+        if let Err(e) = self.write(url, response) {
+            warn!("failed to write cache entry for {}: {}", url, e);
+        }
+    }
+}
+
+/// stable across runs and Rust versions, unlike `DefaultHasher`
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Wraps any client, answering from the cache without calling it while entries are younger than
+/// the TTL
+///
+/// Older entries are refreshed through the wrapped client, and when that is a [`DefaultClient`]
+/// sharing the same cache, the refresh is a conditional request that Github doesn't count against
+/// the rate limit if nothing changed
+///
+/// [`DefaultClient`]: crate::client::DefaultClient
+#[derive(Clone)]
+pub struct CachingClient<C> {
+    inner: C,
+    cache: Arc<dyn ResponseCache>,
+    ttl: Duration,
+}
+
+impl<C: GithubClient> CachingClient<C> {
+    pub fn new(inner: C, cache: Arc<dyn ResponseCache>, ttl: Duration) -> Self {
+        Self { inner, cache, ttl }
+    }
+
+    /// the cached value for `key` while it is fresh, otherwise the result of `fetch`, which is
+    /// stored if it succeeds
+    async fn cached<T, F>(&self, key: String, fetch: F) -> Result<T, Error>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<T, Error>>,
+    {
+        if let Some(value) = self.fresh(&key) {
+            return Ok(value);
+        }
+        let value = fetch.await?;
+        self.store(&key, &value)?;
+        Ok(value)
+    }
+
+    /// keys are scoped to the wrapped client, so that responses from different APIs don't mix
+    fn key(&self, path: String) -> String {
+        format!("bus-factor:{}/{}", self.inner.cache_scope(), path)
+    }
+
+    fn repo_key(&self, repository: &Repository, kind: &str) -> String {
+        self.key(format!(
+            "repos/{}/{}/{}",
+            repository.owner.login, repository.name, kind
+        ))
+    }
+
+    fn user_key(&self, login: &str, kind: &str) -> String {
+        self.key(format!("users/{}/{}", login.to_lowercase(), kind))
+    }
+
+    fn fresh<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let cached = self.cache.get(key).filter(|c| c.is_fresh(self.ttl))?;
+        debug!("serving from cache: {}", key);
+        serde_json::from_str(&cached.body).ok()
+    }
+
+    fn store<T: Serialize>(&self, key: &str, value: &T) -> Result<(), Error> {
+        let response = CachedResponse {
+            etag: None,
+            last_modified: None,
+            stored_at: Utc::now(),
+            body: serde_json::to_string(value)?,
+        };
+        self.cache.put(key, response);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C: GithubClient> GithubClient for CachingClient<C> {
+    async fn list_repositories<Q>(&self, query: Q) -> Result<Vec<Repository>, Error>
+    where
+        Q: Into<Query> + Send,
+    {
+        let query = query.into();
+        let key = self.key(format!(
+            "search?language={}&limit={}",
+            query.language, query.limit
+        ));
+        self.cached(key, self.inner.list_repositories(query)).await
+    }
+
+    async fn get_repository(&self, repository: &Repository) -> Result<Repository, Error> {
+        let key = self.repo_key(repository, "repository");
+        self.cached(key, self.inner.get_repository(repository))
+            .await
+    }
+
+    async fn list_contributors(&self, repository: &Repository) -> Result<Vec<Contributor>, Error> {
+        let key = self.repo_key(repository, "contributors");
+        self.cached(key, self.inner.list_contributors(repository))
+            .await
+    }
+
+    async fn list_commits(
+        &self,
+        repository: &Repository,
+        window: Window,
+    ) -> Result<Vec<Commit>, Error> {
+        let bound = |time: Option<DateTime<Utc>>| time.map(|t| t.to_rfc3339()).unwrap_or_default();
+        let kind = format!(
+            "commits?since={}&until={}",
+            bound(window.since),
+            bound(window.until)
+        );
+        let key = self.repo_key(repository, &kind);
+        self.cached(key, self.inner.list_commits(repository, window))
+            .await
+    }
+
+    async fn last_commit(
+        &self,
+        repository: &Repository,
+        author: &str,
+    ) -> Result<Option<DateTime<Utc>>, Error> {
+        let kind = format!("last_commit/{}", author.to_lowercase());
+        let key = self.repo_key(repository, &kind);
+        self.cached(key, self.inner.last_commit(repository, author))
+            .await
+    }
+
+    async fn list_line_changes(&self, repository: &Repository) -> Result<Vec<Contributor>, Error> {
+        let key = self.repo_key(repository, "line_changes");
+        self.cached(key, self.inner.list_line_changes(repository))
+            .await
+    }
+
+    async fn list_reviewers(&self, repository: &Repository) -> Result<Vec<Contributor>, Error> {
+        let key = self.repo_key(repository, "reviewers");
+        self.cached(key, self.inner.list_reviewers(repository))
+            .await
+    }
+
+    async fn list_pull_request_authors(
+        &self,
+        repository: &Repository,
+    ) -> Result<Vec<Contributor>, Error> {
+        let key = self.repo_key(repository, "pull_request_authors");
+        self.cached(key, self.inner.list_pull_request_authors(repository))
+            .await
+    }
+
+    async fn get_codeowners(&self, repository: &Repository) -> Result<Option<String>, Error> {
+        let key = self.repo_key(repository, "codeowners");
+        self.cached(key, self.inner.get_codeowners(repository))
+            .await
+    }
+
+    async fn latest_release(
+        &self,
+        repository: &Repository,
+    ) -> Result<Option<DateTime<Utc>>, Error> {
+        let key = self.repo_key(repository, "latest_release");
+        self.cached(key, self.inner.latest_release(repository))
+            .await
+    }
+
+    async fn count_pulls_awaiting_review(&self, repository: &Repository) -> Result<u64, Error> {
+        let key = self.repo_key(repository, "awaiting_review");
+        self.cached(key, self.inner.count_pulls_awaiting_review(repository))
+            .await
+    }
+
+    async fn get_profile(&self, login: &str) -> Result<Profile, Error> {
+        let key = self.user_key(login, "profile");
+        self.cached(key, self.inner.get_profile(login)).await
+    }
+
+    async fn list_organizations(&self, login: &str) -> Result<Vec<String>, Error> {
+        let key = self.user_key(login, "orgs");
+        self.cached(key, self.inner.list_organizations(login)).await
+    }
+
+    async fn list_file_changes(&self, repository: &Repository) -> Result<Vec<FileChange>, Error> {
+        let key = self.repo_key(repository, "file_changes");
+        self.cached(key, self.inner.list_file_changes(repository))
+            .await
+    }
+
+    fn cache_scope(&self) -> String {
+        self.inner.cache_scope()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn response(body: &str) -> CachedResponse {
+        CachedResponse {
+            etag: Some("\"abc\"".into()),
+            last_modified: None,
+            stored_at: Utc::now(),
+            body: body.into(),
+        }
+    }
+
+    #[test]
+    fn memory_cache_round_trip() {
+        let cache = MemoryCache::default();
+        assert_eq!(cache.get("url"), None);
+
+        let stored = response("[]");
+        cache.put("url", stored.clone());
+        assert_eq!(cache.get("url"), Some(stored));
+        assert_eq!(cache.get("other url"), None);
+    }
+
+    #[test]
+    fn disk_cache_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let cache = DiskCache::new(dir.path().join("cache"));
+        assert_eq!(cache.get("url"), None);
+
+        let stored = response("[1, 2, 3]");
+        cache.put("url", stored.clone());
+        assert_eq!(cache.get("url"), Some(stored.clone()));
+        assert_eq!(cache.get("other url"), None);
+
+        // a second instance sees the same entries
+        assert_eq!(
+            DiskCache::new(dir.path().join("cache")).get("url"),
+            Some(stored)
+        );
+    }
+
+    #[test]
+    fn disk_cache_clear() {
+        let dir = TempDir::new().unwrap();
+        let cache = DiskCache::new(dir.path().join("cache"));
+        cache.put("url", response("[]"));
+        cache.clear().unwrap();
+        assert_eq!(cache.get("url"), None);
+
+        // clearing an empty cache is fine
+        cache.clear().unwrap();
+    }
+
+    #[test]
+    fn freshness_respects_ttl() {
+        let mut cached = response("[]");
+        assert!(cached.is_fresh(Duration::from_secs(60)));
+
+        cached.stored_at = Utc::now() - chrono::Duration::seconds(120);
+        assert!(!cached.is_fresh(Duration::from_secs(60)));
+    }
+
+    #[derive(Clone, Default)]
+    struct CountingClient {
+        calls: Arc<AtomicUsize>,
+        scope: &'static str,
+    }
+
+    #[async_trait]
+    impl GithubClient for CountingClient {
+        async fn list_repositories<Q>(&self, _query: Q) -> Result<Vec<Repository>, Error>
+        where
+            Q: Into<Query> + Send,
+        {
+            Ok(vec![]) // not used by these tests
+        }
+
+        async fn list_contributors(
+            &self,
+            _repository: &Repository,
+        ) -> Result<Vec<Contributor>, Error> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(vec![Contributor {
+                login: Some("user".into()),
+                contributions: 1,
+                ..Default::default()
+            }])
+        }
+
+        fn cache_scope(&self) -> String {
+            self.scope.into()
+        }
+    }
+
+    fn repo() -> Repository {
+        Repository {
+            name: "name".into(),
+            owner: Owner {
+                login: "owner".into(),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn caching_client_serves_fresh_entries() {
+        let inner = CountingClient::default();
+        let client = CachingClient::new(
+            inner.clone(),
+            Arc::new(MemoryCache::default()),
+            Duration::from_secs(60),
+        );
+
+        let first = client.list_contributors(&repo()).await.unwrap();
+        let second = client.list_contributors(&repo()).await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn caching_client_refreshes_stale_entries() {
+        let inner = CountingClient::default();
+        let client = CachingClient::new(
+            inner.clone(),
+            Arc::new(MemoryCache::default()),
+            Duration::ZERO,
+        );
+
+        client.list_contributors(&repo()).await.unwrap();
+        client.list_contributors(&repo()).await.unwrap();
+        assert_eq!(inner.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn caching_client_keeps_scopes_apart() {
+        let cache: Arc<dyn ResponseCache> = Arc::new(MemoryCache::default());
+        let public = CountingClient {
+            scope: "https://api.github.com",
+            ..Default::default()
+        };
+        let enterprise = CountingClient {
+            scope: "https://ghe.corp/api/v3",
+            ..public.clone()
+        };
+        let ttl = Duration::from_secs(60);
+
+        CachingClient::new(public, cache.clone(), ttl)
+            .list_contributors(&repo())
+            .await
+            .unwrap();
+        let enterprise = CachingClient::new(enterprise, cache, ttl);
+        enterprise.list_contributors(&repo()).await.unwrap();
+        enterprise.list_contributors(&repo()).await.unwrap();
+        assert_eq!(enterprise.inner.calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -1,27 +1,314 @@
 use crate::{
+    activity::ActivityOptions,
+    affiliation::Affiliations,
     client::GithubClient,
-    model::{Contributor, Repository},
+    codeowners::CodeOwners,
+    filter::BotFilter,
+    identity::Identities,
+    model::{
+        Commit, Concentration, Contributor, OrganizationSummary, OwnerFindings, RankedContributor,
+        RecentSummary, Repository, RepositoryHealth, RiskScore, Window,
+    },
+    ownership::{find_hotspots, OwnershipOptions},
+    risk::RiskWeights,
 };
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::io::Write;
+use std::str::FromStr;
+
+use chrono::{DateTime, Duration, Utc};
 
 use crate::{
     errors::Error,
     model::{Query, RepositorySummary},
 };
 
+/// Settings for how contributors are turned into a summary
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisOptions {
+    pub bot_filter: BotFilter,
+    pub identities: Identities,
+    /// also report the lead contributor's share of the commits made within this window
+    pub window: Option<Window>,
+    pub weighting: Weighting,
+    pub metric: ContributionMetric,
+    pub sort_by: SortBy,
+    /// also look for directories that depend on one person
+    pub ownership: Option<OwnershipOptions>,
+    /// also compare the CODEOWNERS file with recent commits
+    pub code_owners: bool,
+    /// how many of the largest contributors to list for each repo in the table
+    pub top: usize,
+    pub format: OutputFormat,
+    /// also group contributions by company or organization
+    pub affiliations: Option<Affiliations>,
+    /// look up how recently the lead of each repo with a bus factor of 1 committed
+    pub activity: Option<ActivityOptions>,
+    /// also look up the latest release and the pull requests waiting for review
+    pub project_health: bool,
+    pub risk_weights: RiskWeights,
+    /// the lead's share at which a repo has a bus factor of 1, [`DEFAULT_THRESHOLD`] if `None`
+    pub threshold: Option<f64>,
+}
+
+impl AnalysisOptions {
+    fn threshold(&self) -> f64 {
+        self.threshold.unwrap_or(DEFAULT_THRESHOLD)
+    }
+}
+
+/// How the summaries are written out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// a table of the bus factor 1 repositories, for people
+    #[default]
+    Table,
+    /// every repository in full, flagging which have a bus factor of 1
+    Json,
+}
+
+impl OutputFormat {
+    /// the values accepted on the command line
+    pub const VARIANTS: &'static [&'static str] = &["table", "json"];
+
+    /// `top` only limits the contributors listed in the table, JSON has every one of them
+    fn write(
+        self,
+        output: impl Write,
+        results: impl IntoIterator<Item = RepositorySummary>,
+        threshold: f64,
+        top: usize,
+    ) -> Result<(), Error> {
+        match self {
+            Self::Table => format_results(output, results, threshold, top),
+            Self::Json => format_json(output, results, threshold),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            _ => Err(format!(
+                "unknown format {:?}, expected one of: {}",
+                s,
+                Self::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+/// the lead's share at which a repo has a bus factor of 1, unless another is given
+pub const DEFAULT_THRESHOLD: f64 = 0.75;
+
+/// owners without commits in this long are inactive, unless a window is given
+const CODE_OWNER_ACTIVITY_DAYS: i64 = 365;
+
+/// how many half-lives of history are fetched when decaying, after which a commit weighs less than
+/// a millionth of a new one
+const DECAY_HORIZON_HALF_LIVES: i32 = 20;
+
+/// The order repositories are reported in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortBy {
+    /// the order the search returned them in
+    #[default]
+    Search,
+    Name,
+    /// the rest put the most concentrated repositories first
+    Percentage,
+    Gini,
+    Hhi,
+    Entropy,
+    /// the composite risk score, riskiest first
+    Score,
+}
+
+impl SortBy {
+    /// the values accepted on the command line
+    pub const VARIANTS: &'static [&'static str] = &[
+        "search",
+        "name",
+        "percentage",
+        "gini",
+        "hhi",
+        "entropy",
+        "score",
+    ];
+
+    fn sort(self, summaries: &mut [RepositorySummary]) {
+        let descending = |key: fn(&RepositorySummary) -> f64| {
+            move |a: &RepositorySummary, b: &RepositorySummary| key(b).total_cmp(&key(a))
+        };
+        match self {
+            Self::Search => {}
+            Self::Name => summaries.sort_by(|a, b| a.repo_name.cmp(&b.repo_name)),
+            Self::Percentage => summaries.sort_by(descending(|s| s.percentage)),
+            Self::Gini => summaries.sort_by(descending(|s| s.concentration.gini)),
+            Self::Hhi => summaries.sort_by(descending(|s| s.concentration.hhi)),
+            // low entropy means few people do most of the work
+            Self::Entropy => summaries.sort_by(descending(|s| -s.concentration.entropy)),
+            Self::Score => summaries.sort_by(descending(|s| s.risk.score)),
+        }
+    }
+}
+
+impl FromStr for SortBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "search" => Ok(Self::Search),
+            "name" => Ok(Self::Name),
+            "percentage" => Ok(Self::Percentage),
+            "gini" => Ok(Self::Gini),
+            "hhi" => Ok(Self::Hhi),
+            "entropy" => Ok(Self::Entropy),
+            "score" => Ok(Self::Score),
+            _ => Err(format!(
+                "unknown sort order {:?}, expected one of: {}",
+                s,
+                Self::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+/// What counts as a contribution when working out each person's share of a repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContributionMetric {
+    /// commits on the default branch, from the contributors API
+    #[default]
+    Commits,
+    /// additions plus deletions, from the repository statistics
+    LinesChanged,
+    /// merged pull requests reviewed
+    Reviews,
+    /// merged pull requests authored
+    PullRequests,
+}
+
+impl ContributionMetric {
+    /// the values accepted on the command line
+    pub const VARIANTS: &'static [&'static str] = &["commits", "lines", "reviews", "pulls"];
+
+    async fn measure<C: GithubClient>(
+        self,
+        client: &C,
+        repo: &Repository,
+    ) -> Result<Vec<Contributor>, Error> {
+        match self {
+            Self::Commits => client.list_contributors(repo).await,
+            Self::LinesChanged => client.list_line_changes(repo).await,
+            Self::Reviews => client.list_reviewers(repo).await,
+            Self::PullRequests => client.list_pull_request_authors(repo).await,
+        }
+    }
+}
+
+impl fmt::Display for ContributionMetric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Commits => "commits",
+            Self::LinesChanged => "lines changed",
+            Self::Reviews => "reviews",
+            Self::PullRequests => "merged pulls",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for ContributionMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "commits" => Ok(Self::Commits),
+            "lines" => Ok(Self::LinesChanged),
+            "reviews" => Ok(Self::Reviews),
+            "pulls" => Ok(Self::PullRequests),
+            _ => Err(format!(
+                "unknown metric {:?}, expected one of: {}",
+                s,
+                Self::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+/// How much each contribution counts towards a contributor's share
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Weighting {
+    /// every contribution counts the same, using the totals from the contributors API
+    #[default]
+    Count,
+    /// a commit's weight halves every `half_life`, so recent work counts more
+    Decay { half_life: Duration },
+}
+
+/// The searches to run for a report, which may be none when only named repositories are wanted
+pub trait IntoQueries {
+    fn into_queries(self) -> Vec<Query>;
+}
+
+impl<Q: Into<Query>> IntoQueries for Q {
+    fn into_queries(self) -> Vec<Query> {
+        vec![self.into()]
+    }
+}
+
 /// execute a full query, writing a sumamry to the output provided
-pub async fn execute_query<C: GithubClient, W: Write, Q: Into<Query> + Send>(
+pub async fn execute_query<C: GithubClient, W: Write, Q: IntoQueries + Send>(
+    client: C,
+    output: W,
+    query: Q,
+) -> Result<(), Error> {
+    execute_query_with(client, output, query, &AnalysisOptions::default()).await
+}
+
+/// execute a full query with non-default analysis settings
+pub async fn execute_query_with<C: GithubClient, W: Write, Q: IntoQueries + Send>(
     client: C,
     output: W,
     query: Q,
+    options: &AnalysisOptions,
+) -> Result<(), Error> {
+    execute_queries_with(client, output, query.into_queries(), vec![], options).await
+}
+
+/// report on the repositories matching any of the queries together with the named ones, each
+/// only once
+pub async fn execute_queries_with<C: GithubClient, W: Write>(
+    client: C,
+    output: W,
+    queries: Vec<Query>,
+    named: Vec<Repository>,
+    options: &AnalysisOptions,
 ) -> Result<(), Error> {
-    let repos = client.list_repositories(query).await?;
+    let mut repos = vec![];
+    for query in queries {
+        repos.extend(client.list_repositories(query).await?);
+    }
+    for repo in &named {
+        repos.push(client.get_repository(repo).await?);
+    }
+    let mut seen = HashSet::new();
+    repos.retain(|repo| seen.insert((repo.owner.login.clone(), repo.name.clone())));
     info!("found {} matching repositories", repos.len());
+
     let summary_futures = repos
         .into_iter()
-        .map(|repo| process_repo(client.clone(), repo));
-    let summaries = futures::future::try_join_all(summary_futures).await?;
-    format_results(output, summaries)?;
+        .map(|repo| process_repo(client.clone(), repo, options));
+    let mut summaries = futures::future::try_join_all(summary_futures).await?;
+    options.sort_by.sort(&mut summaries);
+    options
+        .format
+        .write(output, summaries, options.threshold(), options.top)?;
     Ok(())
 }
 
@@ -29,45 +316,328 @@ pub async fn execute_query<C: GithubClient, W: Write, Q: Into<Query> + Send>(
 async fn process_repo<C: GithubClient>(
     client: C,
     repo: Repository,
+    options: &AnalysisOptions,
 ) -> Result<RepositorySummary, Error> {
-    let contributors = client.list_contributors(&repo).await?;
-    Ok(summarize(repo.name, contributors))
+    let recent = match options.window {
+        Some(window) => recent_summary(&client, &repo, window, options).await?,
+        None => None,
+    };
+
+    let hotspots = match options.ownership {
+        Some(ownership) => {
+            let changes = client.list_file_changes(&repo).await?;
+            find_hotspots(changes, ownership, &options.bot_filter, &options.identities)
+        }
+        None => vec![],
+    };
+
+    let code_owners = match options.code_owners {
+        true => code_owner_findings(&client, &repo, options).await?,
+        false => None,
+    };
+
+    let summary = match options.weighting {
+        Weighting::Count => count_summary(&client, repo.clone(), options).await?,
+        Weighting::Decay { half_life } => {
+            decay_summary(&client, repo.clone(), half_life, Utc::now(), options).await?
+        }
+    };
+
+    let (latest_release, pulls_awaiting_review) = match options.project_health {
+        true => (
+            client.latest_release(&repo).await?,
+            Some(client.count_pulls_awaiting_review(&repo).await?),
+        ),
+        false => (None, None),
+    };
+
+    let mut summary = RepositorySummary {
+        recent,
+        hotspots,
+        code_owners,
+        health: repo.health.clone(),
+        latest_release,
+        pulls_awaiting_review,
+        ..summary
+    };
+    // only worth a request when the repo depends on one person
+    if let Some(activity) = options.activity {
+        if is_bus_factor_1(&summary, options.threshold()) && !summary.no_data {
+            // the lead may have committed under any of their aliases
+            let mut last_commit = None;
+            for author in options.identities.aliases(&summary.lead_contributor) {
+                last_commit = last_commit.max(client.last_commit(&repo, author).await?);
+            }
+            summary.lead_activity = Some(activity.classify(last_commit, Utc::now()));
+        }
+    }
+    summary.risk = options.risk_weights.score(&summary);
+    Ok(summary)
 }
 
-/// summarize the repo, calculating the ratio from the lead contributor
-fn summarize(
-    repo_name: String,
-    contributors: impl IntoIterator<Item = Contributor>,
-) -> RepositorySummary {
-    let mut max_contributor: Option<Contributor> = None;
-    let mut total_contributions = 0u64;
+/// compare CODEOWNERS with the commits in the analysis window, or the last year without one
+async fn code_owner_findings<C: GithubClient>(
+    client: &C,
+    repo: &Repository,
+    options: &AnalysisOptions,
+) -> Result<Option<OwnerFindings>, Error> {
+    let Some(contents) = client.get_codeowners(repo).await? else {
+        debug!("{} has no CODEOWNERS file", repo.name);
+        return Ok(None);
+    };
+    let code_owners = CodeOwners::parse(&contents)?;
 
-    for c in contributors {
-        total_contributions += c.contributions;
+    let window = options
+        .window
+        .unwrap_or_else(|| code_owner_window(Utc::now()));
+    let commits = client.list_commits(repo, window).await?;
+    Ok(Some(code_owners.findings(&commits)))
+}
 
-        let current_max = match &max_contributor {
-            None => 0,
-            Some(c) => c.contributions,
-        };
+/// the last year, counted from the start of today so repeated runs share cache entries
+fn code_owner_window(now: DateTime<Utc>) -> Window {
+    let today = now.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc();
+    Window {
+        since: Some(today - Duration::days(CODE_OWNER_ACTIVITY_DAYS)),
+        until: None,
+    }
+}
+
+/// weigh every contribution the same, using the totals for the chosen metric
+async fn count_summary<C: GithubClient>(
+    client: &C,
+    repo: Repository,
+    options: &AnalysisOptions,
+) -> Result<RepositorySummary, Error> {
+    let contributors = options.metric.measure(client, &repo).await?;
+    let (contributors, excluded) = options.bot_filter.partition(contributors);
+    if !excluded.is_empty() {
+        debug!("excluded {} accounts from {}", excluded.len(), repo.name);
+    }
+    let contributors = options.identities.merge(contributors);
+    let organization = organization_summary(client, &contributors, options).await?;
+
+    let summary = summarize(repo.name.clone(), contributors).unwrap_or_else(|| no_data(repo));
+    Ok(RepositorySummary {
+        metric: options.metric.to_string(),
+        excluded: excluded.iter().map(|c| c.identity().to_owned()).collect(),
+        organization,
+        ..summary
+    })
+}
+
+/// group contributors by affiliation, if that was requested
+async fn organization_summary<C: GithubClient>(
+    client: &C,
+    contributors: &[Contributor],
+    options: &AnalysisOptions,
+) -> Result<Option<OrganizationSummary>, Error> {
+    match &options.affiliations {
+        Some(affiliations) => affiliations.summarize(client, contributors).await,
+        None => Ok(None),
+    }
+}
+
+/// weigh each commit by its age relative to `now`, from the full commit history
+async fn decay_summary<C: GithubClient>(
+    client: &C,
+    repo: Repository,
+    half_life: Duration,
+    now: DateTime<Utc>,
+    options: &AnalysisOptions,
+) -> Result<RepositorySummary, Error> {
+    let horizon = Window {
+        since: Some(now - half_life * DECAY_HORIZON_HALF_LIVES),
+        until: None,
+    };
+    let mut commits = client.list_commits(&repo, horizon).await?;
+    if commits.is_empty() {
+        // a repo quiet for longer is still measured, from its newest commit
+        commits = client.list_commits(&repo, Window::default()).await?;
+    }
+    let (commits, excluded): (Vec<_>, Vec<_>) = commits
+        .into_iter()
+        .partition(|commit| !options.bot_filter.is_excluded(&commit.author()));
+
+    if !excluded.is_empty() {
+        debug!("excluded {} commits from {}", excluded.len(), repo.name);
+    }
+    let excluded = dedup_in_order(
+        excluded
+            .iter()
+            .map(|commit| commit.author().identity().to_owned()),
+    );
+
+    // affiliations are grouped by commit count, without decay
+    let authors = options
+        .identities
+        .merge(Contributor::tally(commits.iter().map(Commit::author)));
+    let organization = organization_summary(client, &authors, options).await?;
+
+    let weights = decayed_weights(&commits, half_life, now, &options.identities);
+    let summary = summarize_weighted(repo.name.clone(), weights).unwrap_or_else(|| no_data(repo));
+    Ok(RepositorySummary {
+        metric: "decayed commits".into(),
+        excluded,
+        organization,
+        ..summary
+    })
+}
+
+/// the total decayed weight of each canonical author, in order of first appearance
+///
+/// Ages are counted from the newest commit when it is older than `now`, which leaves everyone's
+/// share the same but keeps the weights of a long quiet repo from underflowing to 0.
+fn decayed_weights(
+    commits: &[Commit],
+    half_life: Duration,
+    now: DateTime<Utc>,
+    identities: &Identities,
+) -> Vec<(String, f64)> {
+    let half_life = half_life.num_seconds().max(1) as f64;
+    // commits dated in the future count as brand new
+    let age = |commit: &Commit| (now - commit.timestamp).num_seconds().max(0) as f64;
+    let newest = commits
+        .iter()
+        .map(age)
+        .min_by(f64::total_cmp)
+        .unwrap_or(0.0);
+    let mut weights: Vec<(String, f64)> = vec![];
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for commit in commits {
+        let weight = 0.5f64.powf((age(commit) - newest) / half_life);
+
+        let author = commit.author();
+        let identity = identities.canonical_contributor(&author);
+        match positions.get(&identity.to_lowercase()) {
+            Some(&index) => weights[index].1 += weight,
+            None => {
+                positions.insert(identity.to_lowercase(), weights.len());
+                weights.push((identity.to_owned(), weight));
+            }
+        }
+    }
+    weights
+}
 
-        if c.contributions > current_max {
-            max_contributor = Some(c);
+fn dedup_in_order(identities: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut unique = vec![];
+    for identity in identities {
+        if !unique.contains(&identity) {
+            unique.push(identity);
         }
     }
+    unique
+}
 
-    let max_contributor = max_contributor.expect("no contributors found");
+/// the lead contributor of the commits within the window, or `None` if nobody committed in it
+async fn recent_summary<C: GithubClient>(
+    client: &C,
+    repo: &Repository,
+    window: Window,
+    options: &AnalysisOptions,
+) -> Result<Option<RecentSummary>, Error> {
+    let commits = client.list_commits(repo, window).await?;
+    let authors = Contributor::tally(commits.iter().map(Commit::author));
+    let (authors, _) = options.bot_filter.partition(authors);
+    let authors = options.identities.merge(authors);
+    let Some(summary) = summarize(repo.name.clone(), authors) else {
+        debug!("no commits to {} within {:?}", repo.name, window);
+        return Ok(None);
+    };
+    Ok(Some(RecentSummary {
+        lead_contributor: summary.lead_contributor,
+        percentage: summary.percentage,
+    }))
+}
 
+/// a summary that says there was nothing to count, e.g. because only bots committed
+fn no_data(repo: Repository) -> RepositorySummary {
+    warn!("{} has no contributions left to count", repo.name);
     RepositorySummary {
-        repo_name,
-        lead_contributor: max_contributor.login,
-        percentage: max_contributor.contributions as f64 / total_contributions as f64,
+        repo_name: repo.name,
+        no_data: true,
+        ..Default::default()
     }
 }
 
+/// summarize the repo, calculating the ratio from the lead contributor, or `None` if nobody
+/// contributed anything
+fn summarize(
+    repo_name: String,
+    contributors: impl IntoIterator<Item = Contributor>,
+) -> Option<RepositorySummary> {
+    let weights = contributors
+        .into_iter()
+        .map(|c| (c.identity().to_owned(), c.contributions as f64));
+    summarize_weighted(repo_name, weights)
+}
+
+/// summarize the repo from each contributor's weight rather than a raw count, ranking everyone
+fn summarize_weighted(
+    repo_name: String,
+    weights: impl IntoIterator<Item = (String, f64)>,
+) -> Option<RepositorySummary> {
+    let weights: Vec<(String, f64)> = weights.into_iter().collect();
+    let total_weight: f64 = weights.iter().map(|(_, weight)| weight).sum();
+    let distribution: Vec<f64> = weights.iter().map(|(_, weight)| *weight).collect();
+
+    let mut top: Vec<RankedContributor> = weights
+        .into_iter()
+        .map(|(identity, weight)| RankedContributor {
+            identity,
+            contributions: weight,
+            share: weight / total_weight,
+        })
+        .collect();
+    // ties are broken by identity, so the result doesn't depend on the order of the API response
+    top.sort_by(|a, b| {
+        b.contributions
+            .total_cmp(&a.contributions)
+            .then_with(|| a.identity.cmp(&b.identity))
+    });
+
+    let lead = top.first().filter(|lead| lead.contributions > 0.0)?;
+    let co_leads = top[1..]
+        .iter()
+        .take_while(|c| is_tied(c.contributions, lead.contributions))
+        .map(|c| c.identity.clone())
+        .collect();
+
+    Some(RepositorySummary {
+        repo_name,
+        lead_contributor: lead.identity.clone(),
+        percentage: lead.share,
+        co_leads,
+        metric: String::new(), // filled in by the caller, which knows what was counted
+        top,
+        excluded: vec![],
+        recent: None,
+        concentration: Concentration::of(&distribution),
+        hotspots: vec![],
+        code_owners: None,
+        organization: None,
+        lead_activity: None,
+        health: RepositoryHealth::default(),
+        latest_release: None,
+        pulls_awaiting_review: None,
+        risk: RiskScore::default(),
+        no_data: false,
+    })
+}
+
+/// whether two totals are the same, allowing for rounding when adding up decayed weights
+fn is_tied(a: f64, b: f64) -> bool {
+    (a - b).abs() <= 1e-9 * a.abs().max(b.abs())
+}
+
 /// Format the results into a pretty-printed string, and write them to the provided output
 fn format_results(
     mut output: impl Write,
     results: impl IntoIterator<Item = RepositorySummary>,
+    threshold: f64,
+    top_count: usize,
 ) -> Result<(), Error> {
     writeln!(
         output,
@@ -75,52 +645,253 @@ fn format_results(
         "project", "user", "percentage"
     )?;
     writeln!(output, "{}", "-".repeat(60))?;
+    let mut elsewhere = vec![];
     for repo in results {
-        if is_bus_factor_1(&repo) {
+        if is_bus_factor_1(&repo, threshold) {
             let RepositorySummary {
                 repo_name,
                 lead_contributor,
                 percentage,
+                co_leads,
+                metric,
+                top,
+                excluded,
+                recent,
+                concentration: _, // left out to keep the table narrow
+                hotspots,
+                code_owners,
+                organization,
+                lead_activity,
+                health,
+                latest_release,
+                pulls_awaiting_review,
+                risk,
+                no_data: _, // never a bus factor of 1
             } = repo;
-            writeln!(
+            write!(
                 output,
                 "{0: <20} | {1: <20} | {2:.2}",
                 repo_name, lead_contributor, percentage
             )?;
+            // only named when it isn't the default, so the usual row is unchanged
+            if !metric.is_empty() && metric != ContributionMetric::default().to_string() {
+                write!(output, " {}", metric)?;
+            }
+            writeln!(output)?;
+            if let Some(activity) = lead_activity {
+                let last_commit = activity.last_commit.map_or_else(
+                    || "no commits found".to_string(),
+                    |date| format!("last commit {}", date.format("%Y-%m-%d")),
+                );
+                writeln!(
+                    output,
+                    "{0: <20}   lead: {1}, {2}",
+                    "", activity.status, last_commit
+                )?;
+            }
+            if !co_leads.is_empty() {
+                writeln!(output, "{0: <20}   co-leads: {1}", "", co_leads.join(", "))?;
+            }
+            if !top.is_empty() && top_count > 0 {
+                let ranked: Vec<_> = top
+                    .iter()
+                    .take(top_count)
+                    .map(|c| format!("{} {:.2}", c.identity, c.share))
+                    .collect();
+                writeln!(output, "{0: <20}   top: {1}", "", ranked.join(", "))?;
+            }
+            if let Some(organization) = organization {
+                writeln!(
+                    output,
+                    "{0: <20}   organization: {1} {2:.2}",
+                    "", organization.lead_organization, organization.percentage
+                )?;
+            }
+            if let Some(recent) = recent {
+                writeln!(
+                    output,
+                    "{0: <20}   recent: {1} {2:.2}",
+                    "", recent.lead_contributor, recent.percentage
+                )?;
+            }
+            if !excluded.is_empty() {
+                writeln!(output, "{0: <20}   excluded: {1}", "", excluded.join(", "))?;
+            }
+            let health = describe_health(&health, latest_release, pulls_awaiting_review);
+            if !health.is_empty() {
+                writeln!(output, "{0: <20}   health: {1}", "", health.join(", "))?;
+            }
+            for hotspot in hotspots {
+                writeln!(
+                    output,
+                    "{0: <20}   hotspot: {1} {2} {3:.2}",
+                    "", hotspot.path, hotspot.owner, hotspot.percentage
+                )?;
+            }
+            if let Some(code_owners) = code_owners {
+                for path in code_owners.single_owner_paths {
+                    writeln!(
+                        output,
+                        "{0: <20}   sole owner: {1} {2}",
+                        "", path.pattern, path.owner
+                    )?;
+                }
+                if !code_owners.inactive_owners.is_empty() {
+                    let inactive = code_owners.inactive_owners.join(", ");
+                    writeln!(output, "{0: <20}   inactive owners: {1}", "", inactive)?;
+                }
+            }
+            if !risk.components.is_empty() {
+                let components: Vec<_> = risk
+                    .components
+                    .iter()
+                    .filter(|c| c.value.is_some())
+                    .map(|c| format!("{} {:.0}", c.name, c.points))
+                    .collect();
+                writeln!(
+                    output,
+                    "{0: <20}   risk: {1:.0} ({2})",
+                    "",
+                    risk.score,
+                    components.join(", ")
+                )?;
+            }
+        } else if !repo.hotspots.is_empty() || is_organization_bus_factor_1(&repo, threshold) {
+            elsewhere.push(repo);
+        }
+    }
+
+    // below the table, so that its rows are still only the repos with a bus factor of 1
+    if !elsewhere.is_empty() {
+        writeln!(output)?;
+        writeln!(output, "concentration in other projects")?;
+    }
+    for repo in elsewhere {
+        if let Some(organization) = &repo.organization {
+            if organization.percentage >= threshold {
+                writeln!(
+                    output,
+                    "{0: <20}   organization: {1} {2:.2}",
+                    repo.repo_name, organization.lead_organization, organization.percentage
+                )?;
+            }
+        }
+        for hotspot in repo.hotspots {
+            writeln!(
+                output,
+                "{0: <20}   hotspot: {1} {2} {3:.2}",
+                repo.repo_name, hotspot.path, hotspot.owner, hotspot.percentage
+            )?;
         }
     }
 
     Ok(())
 }
 
-fn is_bus_factor_1(repo: &RepositorySummary) -> bool {
-    repo.percentage >= 0.75
+/// the parts of a repo's health that are known, nothing at all for local repositories
+fn describe_health(
+    health: &RepositoryHealth,
+    latest_release: Option<DateTime<Utc>>,
+    pulls_awaiting_review: Option<u64>,
+) -> Vec<String> {
+    let mut parts = vec![];
+    if *health != RepositoryHealth::default() {
+        parts.push(format!("{} stars", health.stargazers_count));
+        parts.push(format!("{} forks", health.forks_count));
+        parts.push(format!("{} open issues", health.open_issues_count));
+    }
+    if health.archived {
+        parts.push("archived".into());
+    }
+    if let Some(pushed_at) = health.pushed_at {
+        parts.push(format!("pushed {}", pushed_at.format("%Y-%m-%d")));
+    }
+    if let Some(license) = &health.license {
+        parts.push(
+            license
+                .spdx_id
+                .clone()
+                .unwrap_or_else(|| license.name.clone()),
+        );
+    }
+    if let Some(released) = latest_release {
+        parts.push(format!("released {}", released.format("%Y-%m-%d")));
+    }
+    if let Some(count) = pulls_awaiting_review {
+        parts.push(format!("{} pulls awaiting review", count));
+    }
+    parts
+}
+
+/// Write every summary as a JSON array, with `bus_factor_1` and `organization_bus_factor_1` flags
+/// alongside the summary fields
+fn format_json(
+    mut output: impl Write,
+    results: impl IntoIterator<Item = RepositorySummary>,
+    threshold: f64,
+) -> Result<(), Error> {
+    #[derive(serde::Serialize)]
+    struct Report {
+        #[serde(flatten)]
+        summary: RepositorySummary,
+        bus_factor_1: bool,
+        organization_bus_factor_1: bool,
+    }
+
+    let reports: Vec<Report> = results
+        .into_iter()
+        .map(|summary| Report {
+            bus_factor_1: is_bus_factor_1(&summary, threshold),
+            organization_bus_factor_1: is_organization_bus_factor_1(&summary, threshold),
+            summary,
+        })
+        .collect();
+    serde_json::to_writer_pretty(&mut output, &reports)?;
+    writeln!(output)?;
+    Ok(())
+}
+
+/// a repo counts if it depends on one person over its lifetime or just recently
+///
+/// directories that depend on one person are reported as hotspots, and an organization that
+/// dominates the repo separately, without making the whole repo count
+fn is_bus_factor_1(repo: &RepositorySummary, threshold: f64) -> bool {
+    let recent = repo.recent.as_ref().map_or(0.0, |recent| recent.percentage);
+    repo.percentage >= threshold || recent >= threshold
+}
+
+/// whether one organization is behind at least the threshold of the contributions
+fn is_organization_bus_factor_1(repo: &RepositorySummary, threshold: f64) -> bool {
+    repo.organization
+        .as_ref()
+        .is_some_and(|org| org.percentage >= threshold)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::{Arc, Mutex};
 
-    use crate::prelude::Owner;
+    use crate::prelude::{
+        ActivityStatus, Commit, FileChange, Hotspot, LeadActivity, License, Owner, SingleOwnerPath,
+    };
 
     use super::*;
 
+    /// how many contributors the tables list, as `--top` does by default
+    const TOP: usize = 3;
+
     #[test]
     fn identifies_bus_factor_1_repos() {
-        assert!(is_bus_factor_1(&RepositorySummary {
+        let with_percentage = |percentage| RepositorySummary {
             repo_name: "name".into(),
             lead_contributor: "name".into(),
-            percentage: 0.75
-        }));
-        assert!(is_bus_factor_1(&RepositorySummary {
-            repo_name: "name".into(),
-            lead_contributor: "name".into(),
-            percentage: 0.9
-        }));
-        assert!(!is_bus_factor_1(&RepositorySummary {
-            repo_name: "name".into(),
-            lead_contributor: "name".into(),
-            percentage: 0.6
-        }));
+            percentage,
+            ..Default::default()
+        };
+        assert!(is_bus_factor_1(&with_percentage(0.75), DEFAULT_THRESHOLD));
+        assert!(is_bus_factor_1(&with_percentage(0.9), DEFAULT_THRESHOLD));
+        assert!(!is_bus_factor_1(&with_percentage(0.6), DEFAULT_THRESHOLD));
+        assert!(is_bus_factor_1(&with_percentage(0.6), 0.5));
     }
 
     #[test]
@@ -132,14 +903,40 @@ mod tests {
                 repo_name: "ripgrep".to_string(),
                 lead_contributor: "burntsushi".to_string(),
                 percentage: 0.888888888,
+                metric: "commits".to_string(),
+                ..Default::default()
             }],
+            DEFAULT_THRESHOLD,
+            TOP,
         )
         .unwrap();
         let string = String::from_utf8(output).unwrap();
-        let line = string.lines().skip(2).next().unwrap(); // skip 2 lines of header
+        let line = string.lines().nth(2).unwrap(); // skip 2 lines of header
         assert!(line.contains("ripgrep"));
         assert!(line.contains("burntsushi"));
         assert!(line.contains("0.89"));
+        assert!(line.ends_with("0.89"));
+    }
+
+    #[test]
+    fn names_metric_other_than_commits() {
+        let mut output = vec![];
+        format_results(
+            &mut output,
+            [RepositorySummary {
+                repo_name: "ripgrep".to_string(),
+                lead_contributor: "burntsushi".to_string(),
+                percentage: 0.888888888,
+                metric: "reviews".to_string(),
+                ..Default::default()
+            }],
+            DEFAULT_THRESHOLD,
+            TOP,
+        )
+        .unwrap();
+        let string = String::from_utf8(output).unwrap();
+        let line = string.lines().nth(2).unwrap();
+        assert!(line.ends_with("0.89 reviews"));
     }
 
     #[test]
@@ -148,10 +945,11 @@ mod tests {
             repo_name: "".into(),
             lead_contributor: "".into(),
             percentage: 0.74,
+            ..Default::default()
         };
 
         let mut output = vec![];
-        format_results(&mut output, [summary]).unwrap();
+        format_results(&mut output, [summary], DEFAULT_THRESHOLD, TOP).unwrap();
         let s = String::from_utf8(output).unwrap();
         assert_eq!(s.lines().collect::<Vec<_>>().len(), 2); // 2 lines from header, rest should be empty
     }
@@ -162,11 +960,13 @@ mod tests {
             repo_name: "".into(),
             lead_contributor: "".into(),
             percentage: 0.74,
+            ..Default::default()
         };
         let printed_sumamry = RepositorySummary {
             repo_name: "repo".into(),
             lead_contributor: "contributor".into(),
             percentage: 0.76,
+            ..Default::default()
         };
         let mut both = vec![];
         let mut only_last = vec![];
@@ -174,76 +974,1034 @@ mod tests {
         format_results(
             &mut both,
             [ignored_summary.clone(), printed_sumamry.clone()],
+            DEFAULT_THRESHOLD,
+            TOP,
         )
         .unwrap();
-        format_results(&mut only_last, [printed_sumamry]).unwrap();
+        format_results(&mut only_last, [printed_sumamry], DEFAULT_THRESHOLD, TOP).unwrap();
 
         assert_eq!(both, only_last);
     }
 
-    fn make_contributors(contributions: impl IntoIterator<Item = u64>) -> Vec<Contributor> {
-        let mut v = vec![];
-        for (index, number) in contributions.into_iter().enumerate() {
-            v.push(Contributor {
-                login: format!("user{}", index),
-                contributions: number,
-            });
-        }
-        v
+    #[test]
+    fn identifies_recent_bus_factor_1_repos() {
+        let summary = RepositorySummary {
+            percentage: 0.3,
+            recent: Some(RecentSummary {
+                lead_contributor: "name".into(),
+                percentage: 0.8,
+            }),
+            ..Default::default()
+        };
+        assert!(is_bus_factor_1(&summary, DEFAULT_THRESHOLD));
     }
 
     #[test]
-    fn correctly_summarizes_repos() {
-        let name = "repo name".to_string();
-        let contributors = make_contributors([1, 2, 3]);
-        let summary = summarize(name, contributors);
-        assert_eq!(
-            summary,
-            RepositorySummary {
-                repo_name: "repo name".to_string(),
-                lead_contributor: "user2".to_string(),
-                percentage: 0.5,
-            }
-        );
+    fn writes_hotspots() {
+        let hotspot = Hotspot {
+            path: "src/parser".into(),
+            owner: "alice".into(),
+            percentage: 0.9,
+            lines: 1000,
+        };
+        let summary = RepositorySummary {
+            repo_name: "repo".to_string(),
+            lead_contributor: "bob".to_string(),
+            percentage: 0.4,
+            hotspots: vec![hotspot],
+            ..Default::default()
+        };
+        assert!(!is_bus_factor_1(&summary, DEFAULT_THRESHOLD));
+
+        let mut output = vec![];
+        format_results(&mut output, [summary.clone()], DEFAULT_THRESHOLD, TOP).unwrap();
+        let string = String::from_utf8(output).unwrap();
+        let lines: Vec<_> = string.lines().collect();
+        assert_eq!(lines[2], "", "no row in the table itself");
+        assert_eq!(lines[3], "concentration in other projects");
+        assert!(lines[4].starts_with("repo "));
+        assert!(lines[4].contains("hotspot: src/parser alice 0.90"));
+
+        let summary = RepositorySummary {
+            percentage: 0.9,
+            ..summary
+        };
+        let mut output = vec![];
+        format_results(&mut output, [summary], DEFAULT_THRESHOLD, TOP).unwrap();
+        let string = String::from_utf8(output).unwrap();
+        let line = string.lines().nth(3).unwrap();
+        assert!(line.contains("hotspot: src/parser alice 0.90"));
+        assert!(!string.contains("other projects"));
     }
 
-    #[test]
-    #[should_panic]
-    fn should_panic_when_no_contributors() {
-        summarize("".into(), make_contributors([]));
+    #[tokio::test]
+    async fn process_repo_finds_hotspots() {
+        let options = AnalysisOptions {
+            ownership: Some(OwnershipOptions { threshold: 0.75 }),
+            ..Default::default()
+        };
+        let summary = process_repo(MockClient, repo(), &options).await.unwrap();
+        assert_eq!(summary.hotspots.len(), 1);
+        assert_eq!(summary.hotspots[0].path, "src");
+        assert_eq!(summary.hotspots[0].owner, "user1");
     }
 
     #[tokio::test]
-    async fn process_repo_summarizes_repo() {
-        let summary = process_repo(
-            MockClient,
-            Repository {
-                name: "repo_name".into(),
-                owner: Owner {
-                    login: "owner".into(),
-                },
-            },
+    async fn process_repo_compares_code_owners() {
+        let options = AnalysisOptions {
+            code_owners: true,
+            ..Default::default()
+        };
+        let summary = process_repo(MockClient, repo(), &options).await.unwrap();
+        let findings = summary.code_owners.unwrap();
+        assert_eq!(findings.single_owner_paths.len(), 2);
+        assert_eq!(findings.single_owner_paths[0].owner, "@user0");
+        assert_eq!(findings.inactive_owners, vec!["@departed"]);
+    }
+
+    #[test]
+    fn code_owner_window_starts_at_midnight() {
+        let morning = code_owner_window("2021-06-01T08:00:00Z".parse().unwrap());
+        let evening = code_owner_window("2021-06-01T20:30:00Z".parse().unwrap());
+        assert_eq!(morning, evening);
+        assert_eq!(morning.since, Some("2020-06-01T00:00:00Z".parse().unwrap()));
+    }
+
+    #[test]
+    fn writes_code_owner_findings() {
+        let mut output = vec![];
+        format_results(
+            &mut output,
+            [RepositorySummary {
+                repo_name: "repo".to_string(),
+                lead_contributor: "user".to_string(),
+                percentage: 0.8,
+                code_owners: Some(OwnerFindings {
+                    single_owner_paths: vec![SingleOwnerPath {
+                        pattern: "/src/".into(),
+                        owner: "@user".into(),
+                    }],
+                    inactive_owners: vec!["@gone".into()],
+                }),
+                ..Default::default()
+            }],
+            DEFAULT_THRESHOLD,
+            TOP,
         )
-        .await
         .unwrap();
-
-        assert_eq!(summary, RepositorySummary {
-            repo_name: "repo_name".into(),
-            lead_contributor: "user3".into(),
-            percentage: 0.4,
-        });
+        let string = String::from_utf8(output).unwrap();
+        assert!(string
+            .lines()
+            .nth(3)
+            .unwrap()
+            .contains("sole owner: /src/ @user"));
+        assert!(string
+            .lines()
+            .nth(4)
+            .unwrap()
+            .contains("inactive owners: @gone"));
     }
 
-    #[derive(Clone)]
-    struct MockClient;
+    #[tokio::test]
+    async fn process_repo_groups_by_affiliation() {
+        let options = AnalysisOptions {
+            affiliations: Some(
+                Affiliations::default()
+                    .assign("user3", "Acme")
+                    .assign("user2", "Acme"),
+            ),
+            ..Default::default()
+        };
+        let summary = process_repo(MockClient, repo(), &options).await.unwrap();
+        let organization = summary.organization.unwrap();
+        assert_eq!(organization.lead_organization, "Acme");
+        assert_eq!(organization.percentage, 0.7);
+    }
+
+    #[test]
+    fn reports_organization_separately() {
+        let summary = RepositorySummary {
+            repo_name: "repo".into(),
+            percentage: 0.3,
+            organization: Some(OrganizationSummary {
+                lead_organization: "Acme".into(),
+                percentage: 0.9,
+                unaffiliated: 0.0,
+            }),
+            ..Default::default()
+        };
+        assert!(!is_bus_factor_1(&summary, DEFAULT_THRESHOLD));
+        assert!(is_organization_bus_factor_1(&summary, DEFAULT_THRESHOLD));
+
+        let mut output = vec![];
+        format_results(&mut output, [summary.clone()], DEFAULT_THRESHOLD, TOP).unwrap();
+        let string = String::from_utf8(output).unwrap();
+        let lines: Vec<_> = string.lines().collect();
+        assert_eq!(lines[2], "", "no row in the table itself");
+        assert_eq!(lines[3], "concentration in other projects");
+        assert!(lines[4].starts_with("repo "));
+        assert!(lines[4].contains("organization: Acme 0.90"));
+
+        let mut output = vec![];
+        OutputFormat::Json
+            .write(&mut output, [summary], DEFAULT_THRESHOLD, TOP)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(json[0]["bus_factor_1"], false);
+        assert_eq!(json[0]["organization_bus_factor_1"], true);
+    }
+
+    #[tokio::test]
+    async fn process_repo_checks_lead_activity() {
+        let options = AnalysisOptions {
+            activity: Some(ActivityOptions::default()),
+            ..Default::default()
+        };
+        let summary = process_repo(MockClient, repo(), &options).await.unwrap();
+        assert_eq!(
+            summary.lead_activity, None,
+            "not looked up below the threshold"
+        );
+
+        let options = AnalysisOptions {
+            identities: Identities::default()
+                .alias("user3", "user0")
+                .alias("user2", "user0"),
+            ..options
+        };
+        let summary = process_repo(MockClient, repo(), &options).await.unwrap();
+        assert_eq!(summary.lead_contributor, "user0");
+        assert_eq!(
+            summary.lead_activity,
+            Some(LeadActivity {
+                last_commit: Some("2021-01-01T00:00:00Z".parse().unwrap()),
+                status: ActivityStatus::Departed,
+            })
+        );
+    }
+
+    #[test]
+    fn writes_lead_activity() {
+        let summary = RepositorySummary {
+            repo_name: "repo".to_string(),
+            lead_contributor: "user".to_string(),
+            percentage: 0.8,
+            lead_activity: Some(LeadActivity {
+                last_commit: Some("2021-03-01T10:00:00Z".parse().unwrap()),
+                status: ActivityStatus::Dormant,
+            }),
+            ..Default::default()
+        };
+        let mut output = vec![];
+        format_results(&mut output, [summary.clone()], DEFAULT_THRESHOLD, TOP).unwrap();
+        let string = String::from_utf8(output).unwrap();
+        let line = string.lines().nth(3).unwrap();
+        assert!(line.contains("lead: dormant, last commit 2021-03-01"));
+
+        let mut output = vec![];
+        OutputFormat::Json
+            .write(&mut output, [summary], DEFAULT_THRESHOLD, TOP)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(json[0]["lead_activity"]["status"], "dormant");
+        assert_eq!(
+            json[0]["lead_activity"]["last_commit"],
+            "2021-03-01T10:00:00Z"
+        );
+    }
+
+    #[tokio::test]
+    async fn process_repo_carries_repository_health() {
+        let repository = Repository {
+            health: RepositoryHealth {
+                stargazers_count: 120,
+                archived: true,
+                ..Default::default()
+            },
+            ..repo()
+        };
+        let options = AnalysisOptions {
+            project_health: true,
+            ..Default::default()
+        };
+        let summary = process_repo(MockClient, repository, &options)
+            .await
+            .unwrap();
+        assert_eq!(summary.health.stargazers_count, 120);
+        assert!(summary.health.archived);
+        assert_eq!(summary.latest_release, None);
+        assert_eq!(summary.pulls_awaiting_review, Some(2));
+    }
+
+    #[test]
+    fn writes_repository_health() {
+        let summary = RepositorySummary {
+            repo_name: "repo".to_string(),
+            lead_contributor: "user".to_string(),
+            percentage: 0.8,
+            health: RepositoryHealth {
+                stargazers_count: 120,
+                forks_count: 8,
+                open_issues_count: 3,
+                archived: true,
+                pushed_at: Some("2021-03-01T10:00:00Z".parse().unwrap()),
+                license: Some(License {
+                    name: "MIT License".into(),
+                    spdx_id: Some("MIT".into()),
+                }),
+            },
+            pulls_awaiting_review: Some(2),
+            ..Default::default()
+        };
+        let mut output = vec![];
+        format_results(&mut output, [summary.clone()], DEFAULT_THRESHOLD, TOP).unwrap();
+        let string = String::from_utf8(output).unwrap();
+        assert_eq!(
+            string.lines().nth(3).unwrap().trim(),
+            "health: 120 stars, 8 forks, 3 open issues, archived, pushed 2021-03-01, MIT, \
+             2 pulls awaiting review"
+        );
+
+        let mut output = vec![];
+        OutputFormat::Json
+            .write(&mut output, [summary], DEFAULT_THRESHOLD, TOP)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(json[0]["health"]["stargazers_count"], 120);
+        assert_eq!(json[0]["health"]["license"]["spdx_id"], "MIT");
+        assert_eq!(json[0]["pulls_awaiting_review"], 2);
+    }
+
+    #[test]
+    fn local_repositories_have_no_health_line() {
+        let mut output = vec![];
+        format_results(
+            &mut output,
+            [RepositorySummary {
+                repo_name: "repo".to_string(),
+                percentage: 0.8,
+                ..Default::default()
+            }],
+            DEFAULT_THRESHOLD,
+            TOP,
+        )
+        .unwrap();
+        assert!(!String::from_utf8(output).unwrap().contains("health"));
+    }
+
+    #[test]
+    fn sorts_by_risk_score() {
+        let scored = |name: &str, score| RepositorySummary {
+            repo_name: name.into(),
+            risk: RiskScore {
+                score,
+                components: vec![],
+            },
+            ..Default::default()
+        };
+        let mut summaries = [scored("low", 10.0), scored("high", 80.0)];
+        SortBy::Score.sort(&mut summaries);
+        assert_eq!(summaries[0].repo_name, "high");
+    }
+
+    #[test]
+    fn explains_risk_score() {
+        let summary = RepositorySummary {
+            repo_name: "repo".to_string(),
+            lead_contributor: "user".to_string(),
+            percentage: 0.8,
+            metric: "commits".into(),
+            ..Default::default()
+        };
+        let summary = RepositorySummary {
+            risk: RiskWeights::default().score(&summary),
+            ..summary
+        };
+        let mut output = vec![];
+        format_results(&mut output, [summary.clone()], DEFAULT_THRESHOLD, TOP).unwrap();
+        let string = String::from_utf8(output).unwrap();
+        assert!(string
+            .lines()
+            .nth(3)
+            .unwrap()
+            .contains("risk: 43 (concentration 43, criticality 0, archived 0)"));
+
+        let mut output = vec![];
+        OutputFormat::Json
+            .write(&mut output, [summary], DEFAULT_THRESHOLD, TOP)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        let score = json[0]["risk"]["score"].as_f64().unwrap();
+        assert!((score - 0.8 * 40.0 / 0.75).abs() < 1e-9);
+        assert!(json[0]["risk"]["components"][1]["value"].is_null());
+        assert_eq!(json[0]["risk"]["components"][0]["name"], "concentration");
+        assert_eq!(
+            json[0]["risk"]["components"][0]["reason"],
+            "user has 0.80 of the commits"
+        );
+        assert_eq!(json[0]["risk"]["components"][3]["reason"], "not archived");
+    }
+
+    #[test]
+    fn writes_recent_lead() {
+        let mut output = vec![];
+        format_results(
+            &mut output,
+            [RepositorySummary {
+                repo_name: "repo".to_string(),
+                lead_contributor: "user".to_string(),
+                percentage: 0.8,
+                recent: Some(RecentSummary {
+                    lead_contributor: "newcomer".into(),
+                    percentage: 0.5,
+                }),
+                ..Default::default()
+            }],
+            DEFAULT_THRESHOLD,
+            TOP,
+        )
+        .unwrap();
+        let string = String::from_utf8(output).unwrap();
+        let line = string.lines().nth(3).unwrap();
+        assert!(line.contains("recent: newcomer 0.50"));
+    }
+
+    fn summary_with(name: &str, percentage: f64, gini: f64, entropy: f64) -> RepositorySummary {
+        RepositorySummary {
+            repo_name: name.into(),
+            percentage,
+            concentration: Concentration {
+                gini,
+                hhi: percentage,
+                entropy,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sorts_summaries() {
+        let summaries = [
+            summary_with("b", 0.5, 0.9, 2.0),
+            summary_with("a", 0.9, 0.1, 0.5),
+            summary_with("c", 0.7, 0.5, 1.0),
+        ];
+        let order = |sort_by: SortBy| {
+            let mut sorted = summaries.clone();
+            sort_by.sort(&mut sorted);
+            sorted.into_iter().map(|s| s.repo_name).collect::<Vec<_>>()
+        };
+
+        assert_eq!(order(SortBy::Search), ["b", "a", "c"]);
+        assert_eq!(order(SortBy::Name), ["a", "b", "c"]);
+        assert_eq!(order(SortBy::Percentage), ["a", "c", "b"]);
+        assert_eq!(order(SortBy::Hhi), ["a", "c", "b"]);
+        assert_eq!(order(SortBy::Gini), ["b", "c", "a"]);
+        assert_eq!(order(SortBy::Entropy), ["a", "c", "b"]);
+    }
+
+    #[test]
+    fn summary_includes_concentration() {
+        let summary = summarize("repo".into(), make_contributors([45, 45, 5, 5])).unwrap();
+        assert_eq!(summary.percentage, 0.45);
+        assert!((summary.concentration.hhi - 0.41).abs() < 1e-9);
+    }
+
+    fn make_contributors(contributions: impl IntoIterator<Item = u64>) -> Vec<Contributor> {
+        let mut v = vec![];
+        for (index, number) in contributions.into_iter().enumerate() {
+            v.push(Contributor {
+                login: Some(format!("user{}", index)),
+                contributions: number,
+                ..Default::default()
+            });
+        }
+        v
+    }
+
+    #[test]
+    fn correctly_summarizes_repos() {
+        let name = "repo name".to_string();
+        let contributors = make_contributors([1, 2, 3]);
+        let summary = summarize(name, contributors).unwrap();
+        assert_eq!(
+            summary,
+            RepositorySummary {
+                repo_name: "repo name".to_string(),
+                lead_contributor: "user2".to_string(),
+                percentage: 0.5,
+                top: vec![
+                    ranked("user2", 3.0, 0.5),
+                    ranked("user1", 2.0, 2.0 / 6.0),
+                    ranked("user0", 1.0, 1.0 / 6.0),
+                ],
+                concentration: Concentration::of(&[1.0, 2.0, 3.0]),
+                ..Default::default()
+            }
+        );
+    }
+
+    fn ranked(identity: &str, contributions: f64, share: f64) -> RankedContributor {
+        RankedContributor {
+            identity: identity.into(),
+            contributions,
+            share,
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_named_repositories_once() {
+        let options = AnalysisOptions {
+            format: OutputFormat::Json,
+            ..Default::default()
+        };
+        let mut output = vec![];
+        execute_queries_with(
+            MockClient,
+            &mut output,
+            vec![],
+            vec![repo(), repo()],
+            &options,
+        )
+        .await
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["repo_name"], "repo_name");
+    }
+
+    #[tokio::test]
+    async fn process_repo_keeps_every_contributor() {
+        let options = AnalysisOptions {
+            top: 2,
+            ..Default::default()
+        };
+        let summary = process_repo(MockClient, repo(), &options).await.unwrap();
+        assert_eq!(
+            summary.top,
+            vec![
+                ranked("user3", 4.0, 0.4),
+                ranked("user2", 3.0, 0.3),
+                ranked("user1", 2.0, 0.2),
+                ranked("user0", 1.0, 0.1),
+            ]
+        );
+    }
+
+    #[test]
+    fn writes_top_contributors() {
+        let summary = RepositorySummary {
+            repo_name: "repo".to_string(),
+            lead_contributor: "user".to_string(),
+            percentage: 0.8,
+            top: vec![ranked("user", 8.0, 0.8), ranked("other", 2.0, 0.2)],
+            ..Default::default()
+        };
+        let line = |top| {
+            let mut output = vec![];
+            format_results(&mut output, [summary.clone()], DEFAULT_THRESHOLD, top).unwrap();
+            String::from_utf8(output)
+                .unwrap()
+                .lines()
+                .nth(3)
+                .unwrap()
+                .to_owned()
+        };
+        assert!(line(TOP).contains("top: user 0.80, other 0.20"));
+        assert!(line(1).ends_with("top: user 0.80"));
+
+        let mut output = vec![];
+        OutputFormat::Json
+            .write(&mut output, [summary], DEFAULT_THRESHOLD, 1)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(json[0]["top"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn writes_every_repo_as_json() {
+        let summaries = [
+            RepositorySummary {
+                repo_name: "risky".to_string(),
+                lead_contributor: "user".to_string(),
+                percentage: 0.8,
+                top: vec![ranked("user", 8.0, 0.8), ranked("other", 2.0, 0.2)],
+                ..Default::default()
+            },
+            RepositorySummary {
+                repo_name: "healthy".to_string(),
+                percentage: 0.3,
+                ..Default::default()
+            },
+        ];
+        let mut output = vec![];
+        OutputFormat::Json
+            .write(&mut output, summaries, DEFAULT_THRESHOLD, TOP)
+            .unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(json[0]["repo_name"], "risky");
+        assert_eq!(json[0]["bus_factor_1"], true);
+        assert_eq!(json[0]["top"][1]["identity"], "other");
+        assert_eq!(json[0]["top"][1]["share"], 0.2);
+        assert_eq!(json[1]["repo_name"], "healthy");
+        assert_eq!(json[1]["bus_factor_1"], false);
+    }
+
+    #[test]
+    fn reports_tied_co_leads() {
+        let contributors = make_contributors([5, 2, 5, 5]);
+        let summary = summarize("repo".into(), contributors).unwrap();
+        assert_eq!(summary.lead_contributor, "user0");
+        assert_eq!(summary.co_leads, vec!["user2", "user3"]);
+        assert_eq!(summary.percentage, 5.0 / 17.0);
+    }
+
+    #[test]
+    fn breaks_ties_by_identity_regardless_of_order() {
+        let contributors = make_contributors([4, 4]);
+        let reversed: Vec<_> = contributors.iter().rev().cloned().collect();
+
+        let summary = summarize("repo".into(), contributors).unwrap();
+        assert_eq!(summarize("repo".into(), reversed).as_ref(), Some(&summary));
+        assert_eq!(summary.lead_contributor, "user0");
+        assert_eq!(summary.co_leads, vec!["user1"]);
+        assert_eq!(summary.percentage, 0.5);
+    }
+
+    #[test]
+    fn near_ties_have_a_single_lead() {
+        let summary = summarize("repo".into(), make_contributors([49, 50, 1])).unwrap();
+        assert_eq!(summary.lead_contributor, "user1");
+        assert!(summary.co_leads.is_empty());
+    }
+
+    #[test]
+    fn weighted_ties_allow_for_rounding() {
+        let summary = summarize_weighted(
+            "repo".into(),
+            [
+                ("b".into(), 0.1 + 0.2),
+                ("a".into(), 0.3),
+                ("c".into(), 0.29),
+            ],
+        )
+        .unwrap();
+        assert_eq!(summary.lead_contributor, "b"); // 0.1 + 0.2 is just over 0.3
+        assert_eq!(summary.co_leads, vec!["a"]);
+    }
+
+    #[test]
+    fn writes_co_leads() {
+        let mut output = vec![];
+        format_results(
+            &mut output,
+            [RepositorySummary {
+                repo_name: "repo".to_string(),
+                lead_contributor: "alice".to_string(),
+                percentage: 0.5,
+                co_leads: vec!["bob".into()],
+                recent: Some(RecentSummary {
+                    lead_contributor: "alice".into(),
+                    percentage: 1.0,
+                }),
+                ..Default::default()
+            }],
+            DEFAULT_THRESHOLD,
+            TOP,
+        )
+        .unwrap();
+        let string = String::from_utf8(output).unwrap();
+        assert!(string.lines().nth(3).unwrap().contains("co-leads: bob"));
+    }
+
+    #[test]
+    fn nothing_to_summarize_without_contributions() {
+        assert_eq!(summarize("".into(), make_contributors([])), None);
+        assert_eq!(summarize("".into(), make_contributors([0, 0])), None);
+    }
+
+    #[test]
+    fn writes_excluded_accounts() {
+        let mut output = vec![];
+        format_results(
+            &mut output,
+            [RepositorySummary {
+                repo_name: "repo".to_string(),
+                lead_contributor: "user".to_string(),
+                percentage: 0.8,
+                metric: "commits".into(),
+                excluded: vec!["dependabot[bot]".into(), "renovate[bot]".into()],
+                ..Default::default()
+            }],
+            DEFAULT_THRESHOLD,
+            TOP,
+        )
+        .unwrap();
+        let string = String::from_utf8(output).unwrap();
+        let line = string.lines().nth(3).unwrap();
+        assert!(line.contains("excluded: dependabot[bot], renovate[bot]"));
+    }
+
+    fn repo() -> Repository {
+        Repository {
+            name: "repo_name".into(),
+            owner: Owner {
+                login: "owner".into(),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn process_repo_excludes_before_calculating() {
+        let options = AnalysisOptions {
+            bot_filter: BotFilter::default().deny("user3"),
+            ..Default::default()
+        };
+        let summary = process_repo(MockClient, repo(), &options).await.unwrap();
+        assert!(summary.risk.score > 0.0);
+
+        assert_eq!(
+            summary,
+            RepositorySummary {
+                repo_name: "repo_name".into(),
+                lead_contributor: "user2".into(),
+                percentage: 0.5,
+                co_leads: vec![],
+                metric: "commits".into(),
+                top: vec![
+                    ranked("user2", 3.0, 0.5),
+                    ranked("user1", 2.0, 2.0 / 6.0),
+                    ranked("user0", 1.0, 1.0 / 6.0),
+                ],
+                excluded: vec!["user3".into()],
+                recent: None,
+                concentration: Concentration::of(&[1.0, 2.0, 3.0]),
+                hotspots: vec![],
+                code_owners: None,
+                organization: None,
+                lead_activity: None,
+                health: RepositoryHealth::default(),
+                latest_release: None,
+                pulls_awaiting_review: None,
+                risk: RiskWeights::default().score(&summary),
+                no_data: false,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn process_repo_reports_repos_with_only_bots() {
+        let options = AnalysisOptions {
+            bot_filter: ["user0", "user1", "user2", "user3"]
+                .into_iter()
+                .fold(BotFilter::default(), BotFilter::deny),
+            activity: Some(ActivityOptions::default()),
+            ..Default::default()
+        };
+        let summary = process_repo(MockClient, repo(), &options).await.unwrap();
+        assert!(summary.no_data);
+        assert_eq!(summary.lead_contributor, "");
+        assert_eq!(summary.excluded.len(), 4);
+        assert_eq!(summary.lead_activity, None);
+        assert!(!is_bus_factor_1(&summary, DEFAULT_THRESHOLD));
+    }
+
+    #[tokio::test]
+    async fn process_repo_merges_identities() {
+        let options = AnalysisOptions {
+            identities: Identities::default()
+                .alias("user0", "user1")
+                .alias("user2", "user1"),
+            ..Default::default()
+        };
+        let summary = process_repo(MockClient, repo(), &options).await.unwrap();
+
+        assert_eq!(summary.lead_contributor, "user1");
+        assert_eq!(summary.percentage, 0.6);
+    }
+
+    #[tokio::test]
+    async fn process_repo_summarizes_repo() {
+        let summary = process_repo(MockClient, repo(), &AnalysisOptions::default())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            summary,
+            RepositorySummary {
+                repo_name: "repo_name".into(),
+                lead_contributor: "user3".into(),
+                percentage: 0.4,
+                metric: "commits".into(),
+                top: vec![
+                    ranked("user3", 4.0, 0.4),
+                    ranked("user2", 3.0, 0.3),
+                    ranked("user1", 2.0, 0.2),
+                    ranked("user0", 1.0, 0.1),
+                ],
+                concentration: Concentration::of(&[1.0, 2.0, 3.0, 4.0]),
+                risk: RiskWeights::default().score(&summary),
+                ..Default::default()
+            }
+        );
+        // only the lead's share of 0.4 counts, at its default weight of 0.4 out of the 0.75 left
+        // once inactivity, which wasn't looked up, is left out
+        assert!((summary.risk.score - 0.4 * 40.0 / 0.75).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn process_repo_uses_metric() {
+        let options = AnalysisOptions {
+            metric: ContributionMetric::Reviews,
+            ..Default::default()
+        };
+        let summary = process_repo(MockClient, repo(), &options).await.unwrap();
+
+        assert_eq!(summary.lead_contributor, "reviewer");
+        assert_eq!(summary.percentage, 1.0);
+        assert_eq!(summary.metric, "reviews");
+    }
+
+    #[tokio::test]
+    async fn metrics_without_data_have_no_data() {
+        for metric in [
+            ContributionMetric::Commits,
+            ContributionMetric::Reviews,
+            ContributionMetric::PullRequests,
+            ContributionMetric::LinesChanged,
+        ] {
+            let options = AnalysisOptions {
+                metric,
+                ..Default::default()
+            };
+            let summary = process_repo(EmptyClient, repo(), &options).await.unwrap();
+            assert!(summary.no_data, "{}", metric);
+            assert_eq!(summary.metric, metric.to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn unsupported_metric_is_an_error() {
+        let options = AnalysisOptions {
+            metric: ContributionMetric::LinesChanged,
+            ..Default::default()
+        };
+        let result = process_repo(MockClient, repo(), &options).await;
+        assert!(matches!(
+            result,
+            Err(Error(crate::errors::ErrorKind::Unsupported(_), _))
+        ));
+    }
+
+    #[test]
+    fn parses_metrics() {
+        for name in ContributionMetric::VARIANTS {
+            assert!(name.parse::<ContributionMetric>().is_ok());
+        }
+        assert!("stars".parse::<ContributionMetric>().is_err());
+    }
+
+    #[tokio::test]
+    async fn process_repo_summarizes_window() {
+        let options = AnalysisOptions {
+            window: Some(Window::default()),
+            ..Default::default()
+        };
+        let summary = process_repo(MockClient, repo(), &options).await.unwrap();
+
+        assert_eq!(summary.lead_contributor, "user3");
+        assert_eq!(
+            summary.recent,
+            Some(RecentSummary {
+                lead_contributor: "user0".into(),
+                percentage: 0.6,
+            })
+        );
+    }
+
+    fn commit_at(login: &str, timestamp: &str) -> Commit {
+        Commit {
+            login: Some(login.into()),
+            name: None,
+            email: None,
+            account_type: None,
+            timestamp: timestamp.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn decayed_weights_halve_every_half_life() {
+        let now = "2021-01-31T00:00:00Z".parse().unwrap();
+        let commits = [
+            commit_at("old", "2021-01-01T00:00:00Z"),
+            commit_at("new", "2021-01-31T00:00:00Z"),
+            commit_at("older", "2020-12-02T00:00:00Z"),
+            commit_at("future", "2021-02-10T00:00:00Z"),
+        ];
+        let weights = decayed_weights(&commits, Duration::days(30), now, &Identities::default());
+        assert_eq!(
+            weights,
+            vec![
+                ("old".into(), 0.5),
+                ("new".into(), 1.0),
+                ("older".into(), 0.25),
+                ("future".into(), 1.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn decayed_weights_merge_identities() {
+        let now = "2021-01-31T00:00:00Z".parse().unwrap();
+        let commits = [
+            commit_at("jdoe", "2021-01-31T00:00:00Z"),
+            commit_at("bob", "2021-01-31T00:00:00Z"),
+            commit_at("jane-work", "2021-01-01T00:00:00Z"),
+        ];
+        let identities = Identities::default().alias("jane-work", "jdoe");
+        let weights = decayed_weights(&commits, Duration::days(30), now, &identities);
+        assert_eq!(weights, vec![("jdoe".into(), 1.5), ("bob".into(), 1.0)]);
+    }
+
+    #[test]
+    fn decayed_weights_of_old_commits_dont_underflow() {
+        // 0.5^1460 is too small for an f64, counted from now every weight would be 0
+        let now = "2025-01-01T00:00:00Z".parse().unwrap();
+        let commits = [
+            commit_at("alice", "2021-01-02T00:00:00Z"),
+            commit_at("bob", "2021-01-01T00:00:00Z"),
+            commit_at("alice", "2021-01-02T00:00:00Z"),
+        ];
+        let weights = decayed_weights(&commits, Duration::days(1), now, &Identities::default());
+        assert_eq!(weights, vec![("alice".into(), 2.0), ("bob".into(), 0.5)]);
+
+        let summary = summarize_weighted("repo".into(), weights).unwrap();
+        assert_eq!(summary.lead_contributor, "alice");
+        assert_eq!(summary.percentage, 0.8);
+    }
+
+    #[tokio::test]
+    async fn decay_without_commits_has_no_data() {
+        let options = AnalysisOptions {
+            bot_filter: ["user0", "user1", "user2"]
+                .into_iter()
+                .fold(BotFilter::default(), BotFilter::deny),
+            ..Default::default()
+        };
+        let now = "2021-01-01T00:00:00Z".parse().unwrap();
+        let summary = decay_summary(&MockClient, repo(), Duration::days(1), now, &options)
+            .await
+            .unwrap();
+        assert!(summary.no_data);
+        assert_eq!(summary.metric, "decayed commits");
+    }
+
+    #[test]
+    fn summarizes_weighted_contributions() {
+        let summary =
+            summarize_weighted("repo".into(), [("a".into(), 0.5), ("b".into(), 1.5)]).unwrap();
+        assert_eq!(summary.lead_contributor, "b");
+        assert_eq!(summary.percentage, 0.75);
+    }
+
+    #[tokio::test]
+    async fn decay_favours_recent_commits() {
+        // user0 made 3 of the 4 commits, but user1's is the only recent one
+        let options = AnalysisOptions {
+            bot_filter: BotFilter::default().deny("user2"),
+            ..Default::default()
+        };
+        let now = "2021-01-01T00:00:00Z".parse().unwrap();
+        let summary = decay_summary(&MockClient, repo(), Duration::days(1), now, &options)
+            .await
+            .unwrap();
+
+        assert_eq!(summary.lead_contributor, "user1");
+        assert!(summary.percentage > 0.99);
+        assert_eq!(summary.excluded, vec!["user2"]);
+    }
+
+    #[tokio::test]
+    async fn decay_only_fetches_recent_history() {
+        let windows = Arc::new(Mutex::new(vec![]));
+        let client = WindowClient(windows.clone());
+        let options = AnalysisOptions::default();
+
+        let now = "2021-01-01T00:00:00Z".parse().unwrap();
+        let summary = decay_summary(&client, repo(), Duration::days(1), now, &options)
+            .await
+            .unwrap();
+        assert_eq!(summary.lead_contributor, "user1");
+        assert_eq!(summary.percentage, 1.0);
+        assert_eq!(
+            *windows.lock().unwrap(),
+            vec![Window {
+                since: Some("2020-12-12T00:00:00Z".parse().unwrap()),
+                until: None,
+            }]
+        );
+
+        // with nothing that recent, the whole history is fetched after all
+        windows.lock().unwrap().clear();
+        let now = "2030-01-01T00:00:00Z".parse().unwrap();
+        let summary = decay_summary(&client, repo(), Duration::days(1), now, &options)
+            .await
+            .unwrap();
+        assert_eq!(summary.lead_contributor, "user1");
+        assert_eq!(windows.lock().unwrap().len(), 2);
+        assert_eq!(windows.lock().unwrap()[1], Window::default());
+    }
+
+    /// serves the same history as [`MockClient`] without bots, within the window asked for
+    #[derive(Clone)]
+    struct WindowClient(Arc<Mutex<Vec<Window>>>);
 
     #[async_trait]
-    impl GithubClient for MockClient {
-        async fn list_repositories<Q: Into<Query> + Send>(
+    impl GithubClient for WindowClient {
+        async fn list_repositories<Q>(&self, _query: Q) -> Result<Vec<Repository>, Error>
+        where
+            Q: Into<Query> + Send,
+        {
+            Ok(vec![])
+        }
+
+        async fn list_contributors(
+            &self,
+            _repository: &Repository,
+        ) -> Result<Vec<Contributor>, Error> {
+            Ok(vec![])
+        }
+
+        async fn list_commits(
             &self,
-            _query: Q,
-        ) -> Result<Vec<Repository>, Error> {
-            unimplemented!()
+            _repository: &Repository,
+            window: Window,
+        ) -> Result<Vec<Commit>, Error> {
+            self.0.lock().unwrap().push(window);
+            Ok([
+                commit_at("user1", "2021-01-01T00:00:00Z"),
+                commit_at("user0", "2020-01-01T00:00:00Z"),
+                commit_at("user0", "2020-01-01T00:00:00Z"),
+                commit_at("user0", "2020-01-01T00:00:00Z"),
+            ]
+            .into_iter()
+            .filter(|commit| window.contains(commit.timestamp))
+            .collect())
+        }
+    }
+
+    #[derive(Clone)]
+    struct MockClient;
+
+    #[async_trait]
+    impl GithubClient for MockClient {
+        async fn list_repositories<Q>(&self, _query: Q) -> Result<Vec<Repository>, Error>
+        where
+            Q: Into<Query> + Send,
+        {
+            Ok(vec![]) // not used by these tests
         }
 
         async fn list_contributors(
@@ -252,5 +2010,102 @@ mod tests {
         ) -> Result<Vec<Contributor>, Error> {
             Ok(make_contributors([1, 2, 3, 4]))
         }
+
+        async fn list_commits(
+            &self,
+            _repository: &Repository,
+            _window: Window,
+        ) -> Result<Vec<Commit>, Error> {
+            Ok(vec![
+                commit_at("user2", "2021-01-01T00:00:00Z"),
+                commit_at("user1", "2021-01-01T00:00:00Z"),
+                commit_at("user0", "2020-01-01T00:00:00Z"),
+                commit_at("user0", "2020-01-01T00:00:00Z"),
+                commit_at("user0", "2020-01-01T00:00:00Z"),
+            ])
+        }
+
+        async fn latest_release(
+            &self,
+            _repository: &Repository,
+        ) -> Result<Option<DateTime<Utc>>, Error> {
+            Ok(None)
+        }
+
+        async fn count_pulls_awaiting_review(
+            &self,
+            _repository: &Repository,
+        ) -> Result<u64, Error> {
+            Ok(2)
+        }
+
+        async fn get_codeowners(&self, _repository: &Repository) -> Result<Option<String>, Error> {
+            Ok(Some(
+                "* @user0 @org/team\n/docs/ @user0\n/old/ @departed\n".into(),
+            ))
+        }
+
+        async fn list_file_changes(
+            &self,
+            _repository: &Repository,
+        ) -> Result<Vec<FileChange>, Error> {
+            Ok(vec![FileChange {
+                path: "src/lib.rs".into(),
+                author: make_contributors([0, 500]).remove(1),
+            }])
+        }
+
+        async fn list_reviewers(
+            &self,
+            _repository: &Repository,
+        ) -> Result<Vec<Contributor>, Error> {
+            Ok(vec![Contributor {
+                login: Some("reviewer".into()),
+                contributions: 3,
+                ..Default::default()
+            }])
+        }
+    }
+
+    /// an empty repo, without commits or merged pull requests, whose line statistics are zero
+    #[derive(Clone)]
+    struct EmptyClient;
+
+    #[async_trait]
+    impl GithubClient for EmptyClient {
+        async fn list_repositories<Q>(&self, _query: Q) -> Result<Vec<Repository>, Error>
+        where
+            Q: Into<Query> + Send,
+        {
+            Ok(vec![])
+        }
+
+        async fn list_contributors(
+            &self,
+            _repository: &Repository,
+        ) -> Result<Vec<Contributor>, Error> {
+            Ok(vec![])
+        }
+
+        async fn list_line_changes(
+            &self,
+            _repository: &Repository,
+        ) -> Result<Vec<Contributor>, Error> {
+            Ok(make_contributors([0]))
+        }
+
+        async fn list_reviewers(
+            &self,
+            _repository: &Repository,
+        ) -> Result<Vec<Contributor>, Error> {
+            Ok(vec![])
+        }
+
+        async fn list_pull_request_authors(
+            &self,
+            _repository: &Repository,
+        ) -> Result<Vec<Contributor>, Error> {
+            Ok(vec![])
+        }
     }
 }
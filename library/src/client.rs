@@ -1,45 +1,394 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
+use base64::prelude::*;
+use chrono::{DateTime, Utc};
 use reqwest::{
-    header::{ACCEPT, AUTHORIZATION, USER_AGENT},
-    RequestBuilder,
+    header::{
+        HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH,
+        LAST_MODIFIED, USER_AGENT,
+    },
+    Certificate, Proxy, Request, Response, StatusCode,
 };
 use secrecy::ExposeSecret;
+use serde::de::DeserializeOwned;
+use tokio::sync::{Mutex, Semaphore};
+use tokio::time::Instant;
 
-use crate::{errors::Error, model::*};
+use crate::{
+    cache::{CachedResponse, ResponseCache},
+    codeowners::CODEOWNERS_PATHS,
+    errors::{Error, ErrorKind},
+    model::*,
+    retry::RetryPolicy,
+};
 
 #[async_trait]
-pub trait GithubClient: Clone {
-    async fn list_repositories<Q: Into<Query> + Send>(
-        &self,
-        query: Q,
-    ) -> Result<Vec<Repository>, Error>;
+pub trait GithubClient: Clone + Send + Sync {
+    async fn list_repositories<Q>(&self, query: Q) -> Result<Vec<Repository>, Error>
+    where
+        Q: Into<Query> + Send;
 
     async fn list_contributors(&self, repository: &Repository) -> Result<Vec<Contributor>, Error>;
+
+    /// a repository named by the user, with the details a search would have returned
+    ///
+    /// Clients without those details return it as it was named.
+    async fn get_repository(&self, repository: &Repository) -> Result<Repository, Error> {
+        Ok(repository.clone())
+    }
+
+    /// every commit on the default branch within the window, for time based analysis
+    async fn list_commits(
+        &self,
+        repository: &Repository,
+        window: Window,
+    ) -> Result<Vec<Commit>, Error> {
+        let _ = (repository, window);
+        bail!(ErrorKind::Unsupported("listing commits".into()))
+    }
+
+    /// when `author` last committed to the default branch, if ever
+    ///
+    /// The author is matched against the login, name or email of each commit.
+    async fn last_commit(
+        &self,
+        repository: &Repository,
+        author: &str,
+    ) -> Result<Option<DateTime<Utc>>, Error> {
+        let commits = self.list_commits(repository, Window::default()).await?;
+        Ok(commits
+            .iter()
+            .filter(|commit| {
+                [&commit.login, &commit.name, &commit.email]
+                    .into_iter()
+                    .flatten()
+                    .any(|identity| identity.eq_ignore_ascii_case(author))
+            })
+            .map(|commit| commit.timestamp)
+            .max())
+    }
+
+    /// additions plus deletions per author, from the repository statistics
+    async fn list_line_changes(&self, repository: &Repository) -> Result<Vec<Contributor>, Error> {
+        let _ = repository;
+        bail!(ErrorKind::Unsupported("listing line changes".into()))
+    }
+
+    /// the number of merged pull requests each reviewer reviewed
+    async fn list_reviewers(&self, repository: &Repository) -> Result<Vec<Contributor>, Error> {
+        let _ = repository;
+        bail!(ErrorKind::Unsupported(
+            "listing pull request reviews".into()
+        ))
+    }
+
+    /// the number of merged pull requests each author opened
+    async fn list_pull_request_authors(
+        &self,
+        repository: &Repository,
+    ) -> Result<Vec<Contributor>, Error> {
+        let _ = repository;
+        bail!(ErrorKind::Unsupported("listing pull requests".into()))
+    }
+
+    /// the contents of the CODEOWNERS file on the default branch, if there is one
+    async fn get_codeowners(&self, repository: &Repository) -> Result<Option<String>, Error> {
+        let _ = repository;
+        bail!(ErrorKind::Unsupported("reading CODEOWNERS".into()))
+    }
+
+    /// lines changed per file and author, for finding directories that depend on one person
+    async fn list_file_changes(&self, repository: &Repository) -> Result<Vec<FileChange>, Error> {
+        let _ = repository;
+        bail!(ErrorKind::Unsupported("listing file changes".into()))
+    }
+
+    /// when the latest release was published, if there is one
+    async fn latest_release(
+        &self,
+        repository: &Repository,
+    ) -> Result<Option<DateTime<Utc>>, Error> {
+        let _ = repository;
+        bail!(ErrorKind::Unsupported("reading releases".into()))
+    }
+
+    /// the number of open pull requests, not counting drafts, that nobody has reviewed yet
+    async fn count_pulls_awaiting_review(&self, repository: &Repository) -> Result<u64, Error> {
+        let _ = repository;
+        bail!(ErrorKind::Unsupported(
+            "counting pull requests awaiting review".into()
+        ))
+    }
+
+    async fn get_profile(&self, login: &str) -> Result<Profile, Error> {
+        let _ = login;
+        bail!(ErrorKind::Unsupported("reading user profiles".into()))
+    }
+
+    /// the organizations a user has made their membership of public
+    async fn list_organizations(&self, login: &str) -> Result<Vec<String>, Error> {
+        let _ = login;
+        bail!(ErrorKind::Unsupported(
+            "listing organization memberships".into()
+        ))
+    }
+
+    /// what sets this client's answers apart from another's to the same calls, such as the API it
+    /// talks to, so that a shared cache keeps them apart
+    ///
+    /// scopes made of several parts separate them with spaces, which can't appear in a URL
+    fn cache_scope(&self) -> String {
+        String::new()
+    }
+}
+
+/// The account attached to a commit, pull request or review
+#[derive(Debug, serde::Deserialize)]
+struct Account {
+    login: String,
+    #[serde(rename = "type")]
+    account_type: Option<String>,
+}
+
+impl From<Account> for Contributor {
+    fn from(account: Account) -> Self {
+        Contributor {
+            login: Some(account.login),
+            account_type: account.account_type,
+            contributions: 1,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CommitResponse {
+    sha: String,
+    commit: CommitDetails,
+    author: Option<Account>, // null when the email isn't linked to an account
+    #[serde(default)]
+    files: Vec<FileStats>, // only present when fetching a single commit
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct CommitDetails {
+    author: Option<Signature>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct Signature {
+    name: Option<String>,
+    email: Option<String>,
+    date: DateTime<Utc>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct FileStats {
+    filename: String,
+    additions: u64,
+    deletions: u64,
+}
+
+impl CommitResponse {
+    fn into_commit(self) -> Option<Commit> {
+        let signature = self.commit.author?;
+        let (login, account_type) = match self.author {
+            Some(account) => (Some(account.login), account.account_type),
+            None => (None, None),
+        };
+        Some(Commit {
+            login,
+            name: signature.name,
+            email: signature.email,
+            account_type,
+            timestamp: signature.date,
+        })
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+struct PullRequest {
+    number: u64,
+    user: Option<Account>, // null for deleted accounts
+    merged_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Clone)]
 pub struct DefaultClient {
-    api_key: ApiKey,
+    base_url: String,
+    headers: HeaderMap,    // validated once by the builder, includes the API key
+    http: reqwest::Client, // shared so connections and TLS sessions are pooled
+    retry: RetryPolicy,
+    permits: Option<Arc<Semaphore>>,
+    cache: Option<Arc<dyn ResponseCache>>,
+    anonymous: bool,
+    search_interval: Duration,
+    last_search: Arc<Mutex<Option<Instant>>>,
 }
 
-const REPO_SEARCH_URL: &str = "https://api.github.com/search/repositories";
+/// the public Github API, Github Enterprise Server instances are usually at `https://<host>/api/v3`
+pub const DEFAULT_BASE_URL: &str = "https://api.github.com";
+pub const DEFAULT_USER_AGENT: &str = concat!("bus-factor/", env!("CARGO_PKG_VERSION"));
 const V3_API_STR: &str = "application/vnd.github.v3+json";
+const PER_PAGE: usize = 100; // the maximum the API allows
+const MAX_PAGES: usize = 100; // guards against listing the entire history of huge repositories
+const REVIEWED_PULLS: usize = 100;
+const DIFFED_COMMITS: usize = 300; // file stats need a request per commit
+const STATS_POLLS: u32 = 6; // with the default backoff, about half a minute for statistics
+/// the search API allows 30 requests a minute, far fewer than the rest of the API
+pub const DEFAULT_SEARCH_INTERVAL: Duration = Duration::from_secs(2);
 
 impl DefaultClient {
-    pub fn create(api_key: ApiKey) -> Self {
-        Self { api_key }
+    /// create a client with the default settings, failing if the TLS backend cannot be initialized
+    pub fn create(api_key: ApiKey) -> Result<Self, Error> {
+        Self::builder(api_key).build()
+    }
+
+    pub fn builder(api_key: ApiKey) -> DefaultClientBuilder {
+        DefaultClientBuilder::new(api_key)
     }
 
-    fn build_default_request(&self, s: impl AsRef<str>) -> RequestBuilder {
-        debug!("creating request builder for url: {}", s.as_ref());
-        let auth = format!("token {}", self.api_key.expose_secret());
+    /// follow `page` until a short page, up to [`MAX_PAGES`]
+    async fn get_pages<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        query: Vec<(&'static str, String)>,
+    ) -> Result<Vec<T>, Error> {
+        let mut items = vec![];
+        for page in 1..=MAX_PAGES {
+            let mut query = query.clone();
+            query.push(("per_page", PER_PAGE.to_string()));
+            query.push(("page", page.to_string()));
+            let response: Vec<T> = self.get_json(url, &query).await?;
+            let last_page = response.len() < PER_PAGE;
+            items.extend(response);
+
+            if last_page {
+                return Ok(items);
+            }
+        }
 
-        reqwest::Client::new()
-            .get(s.as_ref())
-            .header(USER_AGENT, "rust") // github requires user agent headers
-            .header(ACCEPT, V3_API_STR) // explicitly set v3 API, recommended
-            .header(AUTHORIZATION, auth)
+        warn!("stopped listing {} after {} pages", url, MAX_PAGES);
+        Ok(items)
+    }
+
+    /// closed pull requests that were merged, most recently created first
+    async fn list_merged_pulls(&self, repo: &Repository) -> Result<Vec<PullRequest>, Error> {
+        let query = vec![("state", "closed".to_string())];
+        let pulls: Vec<PullRequest> = self.get_pages(&self.get_pulls_url(repo), query).await?;
+        Ok(pulls
+            .into_iter()
+            .filter(|pull| pull.merged_at.is_some())
+            .collect())
+    }
+
+    /// a GET request to the search API, waiting until `search_interval` after the previous one
+    async fn search_json<T: DeserializeOwned>(
+        &self,
+        url: impl AsRef<str>,
+        query: &[(&str, String)],
+    ) -> Result<T, Error> {
+        {
+            // held while waiting, so concurrent searches queue up one interval apart
+            let mut last_search = self.last_search.lock().await;
+            if let Some(last_search) = *last_search {
+                tokio::time::sleep_until(last_search + self.search_interval).await;
+            }
+            *last_search = Some(Instant::now());
+        }
+        self.get_json(url, query).await
+    }
+
+    /// perform a GET request, applying the concurrency limit, retry policy and cache
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: impl AsRef<str>,
+        query: &[(&str, String)],
+    ) -> Result<T, Error> {
+        debug!("creating request for url: {}", url.as_ref());
+        let request = self
+            .http
+            .get(url.as_ref())
+            .headers(self.headers.clone())
+            .query(query)
+            .build()?;
+        let key = request.url().to_string();
+
+        let _permit = match &self.permits {
+            Some(permits) => Some(permits.acquire().await.expect("semaphore is never closed")),
+            None => None,
+        };
+
+        let cached = self.cache.as_ref().and_then(|cache| cache.get(&key));
+        let response = self.execute(conditional(request, cached.as_ref())).await?;
+
+        if let (StatusCode::NOT_MODIFIED, Some(cached)) = (response.status(), cached) {
+            debug!("cached response still valid for: {}", key);
+            return Ok(serde_json::from_str(&cached.body)?);
+        }
+        if response.status() == StatusCode::NOT_FOUND {
+            bail!(ErrorKind::NotFound(key));
+        }
+        // statistics endpoints answer 202 while the numbers are still being computed
+        if response.status() == StatusCode::ACCEPTED {
+            bail!(ErrorKind::StatisticsNotReady(key));
+        }
+        if !response.status().is_success() {
+            return Err(api_error(response).await);
+        }
+
+        let etag = header_string(&response, ETAG);
+        let last_modified = header_string(&response, LAST_MODIFIED);
+        let cacheable =
+            response.status().is_success() && (etag.is_some() || last_modified.is_some());
+        let body = response.text().await?;
+        let parsed = serde_json::from_str(&body)?;
+
+        if let (Some(cache), true) = (&self.cache, cacheable) {
+            let entry = CachedResponse {
+                etag,
+                last_modified,
+                stored_at: Utc::now(),
+                body,
+            };
+            cache.put(&key, entry);
+        }
+        Ok(parsed)
+    }
+
+    async fn execute(&self, request: Request) -> Result<Response, Error> {
+        let mut attempt = 0;
+        loop {
+            let retry = request
+                .try_clone()
+                .expect("GET requests have no streaming body");
+            let result = self.http.execute(retry).await;
+            let (retryable, delay) = match &result {
+                Ok(response) => (
+                    RetryPolicy::should_retry_response(response.status(), response.headers()),
+                    self.retry.delay(attempt, response.headers(), Utc::now()),
+                ),
+                Err(e) => (
+                    RetryPolicy::should_retry_error(e),
+                    self.retry.backoff(attempt),
+                ),
+            };
+
+            if !retryable || attempt >= self.retry.max_retries {
+                return Ok(result?);
+            }
+
+            warn!(
+                "request to {} failed, retrying in {:?}",
+                request.url(),
+                delay
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
     }
 
     fn make_repo_query_params(query: Query) -> HashMap<&'static str, String> {
@@ -50,61 +399,1170 @@ impl DefaultClient {
         map
     }
 
-    fn get_contributors_url(repo: &Repository) -> String {
+    fn repo_search_url(&self) -> String {
+        format!("{}/search/repositories", self.base_url)
+    }
+
+    fn get_contributors_url(&self, repo: &Repository) -> String {
+        format!(
+            "{}/repos/{}/{}/contributors",
+            self.base_url, repo.owner.login, repo.name
+        )
+    }
+
+    fn get_commits_url(&self, repo: &Repository) -> String {
+        format!(
+            "{}/repos/{}/{}/commits",
+            self.base_url, repo.owner.login, repo.name
+        )
+    }
+
+    fn get_stats_url(&self, repo: &Repository) -> String {
+        format!(
+            "{}/repos/{}/{}/stats/contributors",
+            self.base_url, repo.owner.login, repo.name
+        )
+    }
+
+    fn get_contents_url(&self, repo: &Repository, path: &str) -> String {
+        format!(
+            "{}/repos/{}/{}/contents/{}",
+            self.base_url, repo.owner.login, repo.name, path
+        )
+    }
+
+    fn get_user_url(&self, login: &str) -> String {
+        format!("{}/users/{}", self.base_url, login)
+    }
+
+    fn get_pulls_url(&self, repo: &Repository) -> String {
         format!(
-            "https://api.github.com/repos/{}/{}/contributors",
-            repo.owner.login, repo.name
+            "{}/repos/{}/{}/pulls",
+            self.base_url, repo.owner.login, repo.name
         )
     }
+
+    fn get_reviews_url(&self, repo: &Repository, number: u64) -> String {
+        format!("{}/{}/reviews", self.get_pulls_url(repo), number)
+    }
+
+    fn make_commit_query_params(window: Window) -> Vec<(&'static str, String)> {
+        let mut query = vec![];
+        if let Some(since) = window.since {
+            query.push(("since", since.to_rfc3339()));
+        }
+        if let Some(until) = window.until {
+            query.push(("until", until.to_rfc3339()));
+        }
+        query
+    }
+}
+
+/// an unsuccessful response as an error, with the explanation Github gives in its body
+async fn api_error(response: Response) -> Error {
+    #[derive(serde::Deserialize)]
+    struct Body {
+        message: String,
+    }
+
+    let status = response.status();
+    // still limited after the retries, so waiting longer is up to the user
+    let rate_limited = RetryPolicy::is_rate_limited(status, response.headers());
+    let message = match response.text().await {
+        Ok(body) => serde_json::from_str::<Body>(&body)
+            .map(|body| body.message)
+            .unwrap_or(body),
+        Err(e) => e.to_string(),
+    };
+    match rate_limited {
+        true => ErrorKind::RateLimited(message).into(),
+        false => ErrorKind::Api(status.as_u16(), message).into(),
+    }
+}
+
+fn conditional(mut request: Request, cached: Option<&CachedResponse>) -> Request {
+    if let Some(cached) = cached {
+        let headers = request.headers_mut();
+        let etag = cached
+            .etag
+            .as_deref()
+            .and_then(|v| HeaderValue::from_str(v).ok());
+        let modified = cached
+            .last_modified
+            .as_deref()
+            .and_then(|v| HeaderValue::from_str(v).ok());
+        if let Some(etag) = etag {
+            headers.insert(IF_NONE_MATCH, etag);
+        }
+        if let Some(modified) = modified {
+            headers.insert(IF_MODIFIED_SINCE, modified);
+        }
+    }
+    request
+}
+
+fn header_string(response: &Response, name: reqwest::header::HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned)
+}
+
+/// Configures a [`DefaultClient`]
+///
+/// Proxies are read from the `HTTPS_PROXY`/`HTTP_PROXY` env vars unless one is set explicitly
+pub struct DefaultClientBuilder {
+    api_key: ApiKey,
+    base_url: String,
+    user_agent: String,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    proxy: Option<String>,
+    ca_bundle: Option<PathBuf>,
+    gzip: bool,
+    http: Option<reqwest::Client>,
+    retry: RetryPolicy,
+    max_concurrency: Option<usize>,
+    cache: Option<Arc<dyn ResponseCache>>,
+    anonymous: bool,
+    search_interval: Duration,
+}
+
+impl DefaultClientBuilder {
+    fn new(api_key: ApiKey) -> Self {
+        Self {
+            api_key,
+            base_url: DEFAULT_BASE_URL.into(),
+            user_agent: DEFAULT_USER_AGENT.into(),
+            connect_timeout: None,
+            timeout: None,
+            proxy: None,
+            ca_bundle: None,
+            gzip: true,
+            http: None,
+            retry: RetryPolicy::default(),
+            max_concurrency: None,
+            cache: None,
+            anonymous: false,
+            search_interval: DEFAULT_SEARCH_INTERVAL,
+        }
+    }
+
+    /// use a different API root, e.g. for Github Enterprise Server
+    pub fn base_url(mut self, base_url: impl AsRef<str>) -> Self {
+        self.base_url = base_url.as_ref().trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Github rejects requests without a user agent, so this can be changed but not removed
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// limit the time taken to establish a connection
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// limit the time taken by a whole request, including reading the response body
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// send all requests through this proxy, overriding the env vars
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    /// trust the PEM encoded certificates in this file, in addition to the system roots
+    pub fn ca_bundle(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_bundle = Some(path.into());
+        self
+    }
+
+    /// request gzip compressed responses, enabled by default
+    pub fn gzip(mut self, enable: bool) -> Self {
+        self.gzip = enable;
+        self
+    }
+
+    /// use an existing client, in which case the timeout, proxy, CA and gzip settings are ignored
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http = Some(client);
+        self
+    }
+
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// limit the number of requests in flight at once, unlimited by default
+    pub fn max_concurrency(mut self, max: usize) -> Self {
+        self.max_concurrency = Some(max);
+        self
+    }
+
+    /// reuse responses from this cache where the API confirms they are unchanged
+    pub fn cache(mut self, cache: Arc<dyn ResponseCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// include commits from emails that aren't linked to a Github account in contributor lists
+    pub fn anonymous_contributors(mut self, enable: bool) -> Self {
+        self.anonymous = enable;
+        self
+    }
+
+    /// the time between requests to the search API, [`DEFAULT_SEARCH_INTERVAL`] by default
+    pub fn search_interval(mut self, interval: Duration) -> Self {
+        self.search_interval = interval;
+        self
+    }
+
+    pub fn build(self) -> Result<DefaultClient, Error> {
+        let mut auth = HeaderValue::from_str(&format!("token {}", self.api_key.expose_secret()))?;
+        auth.set_sensitive(true);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_str(&self.user_agent)?); // github requires user agent headers
+        headers.insert(ACCEPT, HeaderValue::from_static(V3_API_STR)); // explicitly set v3 API, recommended
+        headers.insert(AUTHORIZATION, auth);
+
+        let http = match self.http {
+            Some(http) => http,
+            None => {
+                let mut http = reqwest::Client::builder().gzip(self.gzip);
+                if let Some(timeout) = self.connect_timeout {
+                    http = http.connect_timeout(timeout);
+                }
+                if let Some(timeout) = self.timeout {
+                    http = http.timeout(timeout);
+                }
+                if let Some(proxy) = &self.proxy {
+                    http = http.proxy(Proxy::all(proxy)?);
+                }
+                if let Some(path) = &self.ca_bundle {
+                    for certificate in load_ca_bundle(path)? {
+                        http = http.add_root_certificate(certificate);
+                    }
+                }
+                http.build()?
+            }
+        };
+
+        Ok(DefaultClient {
+            base_url: self.base_url,
+            headers,
+            http,
+            retry: self.retry,
+            permits: self
+                .max_concurrency
+                .map(|max| Arc::new(Semaphore::new(max))),
+            cache: self.cache,
+            anonymous: self.anonymous,
+            search_interval: self.search_interval,
+            last_search: Arc::new(Mutex::new(None)),
+        })
+    }
+}
+
+/// a bundle may contain several certificates, which reqwest only parses one at a time
+fn load_ca_bundle(path: &Path) -> Result<Vec<Certificate>, Error> {
+    const END: &str = "-----END CERTIFICATE-----";
+
+    let contents = std::fs::read_to_string(path)?;
+    let certificates = contents
+        .split_inclusive(END)
+        .filter(|block| block.contains(END))
+        .map(|block| Certificate::from_pem(block.trim().as_bytes()))
+        .collect::<Result<_, _>>()?;
+    Ok(certificates)
 }
 
 #[async_trait]
 impl GithubClient for DefaultClient {
-    async fn list_repositories<Q: Into<Query> + Send>(
-        &self,
-        query: Q,
-    ) -> Result<Vec<Repository>, Error> {
+    async fn list_repositories<Q>(&self, query: Q) -> Result<Vec<Repository>, Error>
+    where
+        Q: Into<Query> + Send,
+    {
         #[derive(serde::Deserialize)]
         struct Response {
             items: Vec<Repository>,
         }
 
-        let query = Self::make_repo_query_params(query.into());
-        let response: Response = self
-            .build_default_request(REPO_SEARCH_URL)
-            .query(&query)
-            .send()
-            .await?
-            .json()
-            .await?;
+        let query: Vec<_> = Self::make_repo_query_params(query.into())
+            .into_iter()
+            .collect();
+        let response: Response = self.search_json(self.repo_search_url(), &query).await?;
 
         Ok(response.items)
     }
 
+    async fn get_repository(&self, repository: &Repository) -> Result<Repository, Error> {
+        let url = format!(
+            "{}/repos/{}/{}",
+            self.base_url, repository.owner.login, repository.name
+        );
+        self.get_json(url, &[]).await
+    }
+
     async fn list_contributors(&self, repository: &Repository) -> Result<Vec<Contributor>, Error> {
-        let url = Self::get_contributors_url(repository);
-        let response = self.build_default_request(url).send().await?.json().await?;
-        Ok(response)
+        let url = self.get_contributors_url(repository);
+        let query = match self.anonymous {
+            true => vec![("anon", "1".to_string())],
+            false => vec![],
+        };
+        self.get_json(url, &query).await
+    }
+
+    async fn list_commits(
+        &self,
+        repository: &Repository,
+        window: Window,
+    ) -> Result<Vec<Commit>, Error> {
+        let url = self.get_commits_url(repository);
+        let responses: Vec<CommitResponse> = self
+            .get_pages(&url, Self::make_commit_query_params(window))
+            .await?;
+
+        Ok(responses
+            .into_iter()
+            .filter_map(CommitResponse::into_commit)
+            .collect())
+    }
+
+    /// the commits API filters by author itself and lists the newest first
+    async fn last_commit(
+        &self,
+        repository: &Repository,
+        author: &str,
+    ) -> Result<Option<DateTime<Utc>>, Error> {
+        let url = self.get_commits_url(repository);
+        let query = [
+            ("author", author.to_string()),
+            ("per_page", "1".to_string()),
+        ];
+        let commits: Vec<CommitResponse> = self.get_json(&url, &query).await?;
+        Ok(commits
+            .into_iter()
+            .find_map(CommitResponse::into_commit)
+            .map(|commit| commit.timestamp))
+    }
+
+    async fn list_line_changes(&self, repository: &Repository) -> Result<Vec<Contributor>, Error> {
+        #[derive(serde::Deserialize)]
+        struct Stats {
+            author: Option<Account>,
+            weeks: Vec<Week>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Week {
+            #[serde(rename = "a")]
+            additions: u64,
+            #[serde(rename = "d")]
+            deletions: u64,
+        }
+
+        let url = self.get_stats_url(repository);
+        let mut attempt = 0;
+        let stats: Vec<Stats> = loop {
+            match self.get_json(&url, &[]).await {
+                Err(Error(ErrorKind::StatisticsNotReady(_), _)) if attempt < STATS_POLLS => {
+                    let delay = self.retry.backoff(attempt);
+                    debug!("statistics for {} not ready, polling in {:?}", url, delay);
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => break result?,
+            }
+        };
+        let contributors = stats.into_iter().filter_map(|stats| {
+            let lines = stats.weeks.iter().map(|w| w.additions + w.deletions).sum();
+            Some(Contributor {
+                contributions: lines,
+                ..stats.author?.into()
+            })
+        });
+        Ok(Contributor::tally(contributors))
+    }
+
+    async fn list_reviewers(&self, repository: &Repository) -> Result<Vec<Contributor>, Error> {
+        #[derive(serde::Deserialize)]
+        struct Review {
+            user: Option<Account>,
+        }
+
+        let pulls = self.list_merged_pulls(repository).await?;
+        // reviews need a request per pull request, so only the most recent are looked at
+        let reviews = pulls.iter().take(REVIEWED_PULLS).map(|pull| async move {
+            let url = self.get_reviews_url(repository, pull.number);
+            let reviews: Vec<Review> = self.get_json(url, &[]).await?;
+            // several rounds of review on one pull request count once
+            let mut reviewers: Vec<Contributor> = vec![];
+            for account in reviews.into_iter().filter_map(|review| review.user) {
+                if !reviewers
+                    .iter()
+                    .any(|r| r.login.as_ref() == Some(&account.login))
+                {
+                    reviewers.push(account.into());
+                }
+            }
+            Ok::<_, Error>(reviewers)
+        });
+        let reviewers = futures::future::try_join_all(reviews).await?;
+        Ok(Contributor::tally(reviewers.into_iter().flatten()))
+    }
+
+    async fn list_pull_request_authors(
+        &self,
+        repository: &Repository,
+    ) -> Result<Vec<Contributor>, Error> {
+        let pulls = self.list_merged_pulls(repository).await?;
+        let authors = pulls
+            .into_iter()
+            .filter_map(|pull| pull.user.map(Contributor::from));
+        Ok(Contributor::tally(authors))
+    }
+
+    async fn get_codeowners(&self, repository: &Repository) -> Result<Option<String>, Error> {
+        #[derive(serde::Deserialize)]
+        struct Contents {
+            content: String,
+        }
+
+        for path in CODEOWNERS_PATHS {
+            let url = self.get_contents_url(repository, path);
+            let contents: Contents = match self.get_json(url, &[]).await {
+                Err(Error(ErrorKind::NotFound(_), _)) => continue,
+                result => result?,
+            };
+            // the encoded content is wrapped over several lines
+            let encoded: String = contents.content.split_whitespace().collect();
+            let decoded = BASE64_STANDARD
+                .decode(encoded)
+                .map_err(|e| format!("invalid contents of {}: {}", path, e))?;
+            return Ok(Some(String::from_utf8_lossy(&decoded).into_owned()));
+        }
+        Ok(None)
+    }
+
+    async fn latest_release(
+        &self,
+        repository: &Repository,
+    ) -> Result<Option<DateTime<Utc>>, Error> {
+        #[derive(serde::Deserialize)]
+        struct Release {
+            published_at: Option<DateTime<Utc>>,
+        }
+
+        let url = format!(
+            "{}/repos/{}/{}/releases/latest",
+            self.base_url, repository.owner.login, repository.name
+        );
+        match self.get_json::<Release>(url, &[]).await {
+            Err(Error(ErrorKind::NotFound(_), _)) => Ok(None),
+            result => Ok(result?.published_at),
+        }
+    }
+
+    /// searched for, since listing pull requests doesn't say whether they have been reviewed
+    async fn count_pulls_awaiting_review(&self, repository: &Repository) -> Result<u64, Error> {
+        #[derive(serde::Deserialize)]
+        struct Response {
+            total_count: u64,
+        }
+
+        let url = format!("{}/search/issues", self.base_url);
+        let query = [
+            (
+                "q",
+                format!(
+                    "repo:{}/{} is:pr is:open draft:false review:none",
+                    repository.owner.login, repository.name
+                ),
+            ),
+            ("per_page", "1".to_string()),
+        ];
+        let response: Response = self.search_json(url, &query).await?;
+        Ok(response.total_count)
+    }
+
+    async fn get_profile(&self, login: &str) -> Result<Profile, Error> {
+        self.get_json(self.get_user_url(login), &[]).await
+    }
+
+    async fn list_organizations(&self, login: &str) -> Result<Vec<String>, Error> {
+        let url = format!("{}/orgs", self.get_user_url(login));
+        let orgs: Vec<Account> = self.get_json(url, &[]).await?;
+        Ok(orgs.into_iter().map(|org| org.login).collect())
+    }
+
+    /// only the most recent commits are diffed, since each needs its own request
+    async fn list_file_changes(&self, repository: &Repository) -> Result<Vec<FileChange>, Error> {
+        let url = self.get_commits_url(repository);
+        let query = vec![("per_page", PER_PAGE.to_string())];
+        let mut shas = vec![];
+        for page in 1..=DIFFED_COMMITS.div_ceil(PER_PAGE) {
+            let mut query = query.clone();
+            query.push(("page", page.to_string()));
+            let commits: Vec<CommitResponse> = self.get_json(&url, &query).await?;
+            let last_page = commits.len() < PER_PAGE;
+            shas.extend(commits.into_iter().map(|commit| commit.sha));
+            if last_page {
+                break;
+            }
+        }
+        shas.truncate(DIFFED_COMMITS);
+
+        let url = &url;
+        let details = shas.iter().map(|sha| async move {
+            let url = format!("{}/{}", url, sha);
+            self.get_json::<CommitResponse>(url, &[]).await
+        });
+        let mut changes = vec![];
+        for mut response in futures::future::try_join_all(details).await? {
+            let files = std::mem::take(&mut response.files);
+            let Some(commit) = response.into_commit() else {
+                continue;
+            };
+            changes.extend(files.into_iter().map(|file| FileChange {
+                path: file.filename,
+                author: Contributor {
+                    contributions: file.additions + file.deletions,
+                    ..commit.author()
+                },
+            }));
+        }
+        Ok(changes)
+    }
+
+    /// anonymous contributors change the contributor lists
+    fn cache_scope(&self) -> String {
+        match self.anonymous {
+            true => format!("{} anonymous", self.base_url),
+            false => self.base_url.clone(),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use microtype::SecretMicrotype;
+
+    use crate::cache::{CachingClient, MemoryCache};
+    use crate::errors::ErrorKind;
+    use crate::tests::{serve, test_file_path, MockResponse};
+
     use super::*;
 
-    #[test]
-    fn correctly_generates_contributors_url() {
-        let repo = Repository {
+    fn client() -> DefaultClient {
+        DefaultClient::create(ApiKey::new("key".into())).unwrap()
+    }
+
+    fn repo() -> Repository {
+        Repository {
             owner: Owner {
                 login: "owner".into(),
             },
             name: "repo_name".into(),
-        };
+            ..Default::default()
+        }
+    }
 
-        let url = DefaultClient::get_contributors_url(&repo);
+    #[test]
+    fn correctly_generates_contributors_url() {
+        let url = client().get_contributors_url(&repo());
         assert_eq!(url, "https://api.github.com/repos/owner/repo_name/contributors");
     }
 
+    #[test]
+    fn correctly_generates_search_url() {
+        assert_eq!(
+            client().repo_search_url(),
+            "https://api.github.com/search/repositories"
+        );
+    }
+
+    #[test]
+    fn urls_use_custom_base_url() {
+        let client = DefaultClient::builder(ApiKey::new("key".into()))
+            .base_url("https://ghe.corp/api/v3/")
+            .build()
+            .unwrap();
+        assert_eq!(
+            client.repo_search_url(),
+            "https://ghe.corp/api/v3/search/repositories"
+        );
+        assert_eq!(
+            client.get_contributors_url(&repo()),
+            "https://ghe.corp/api/v3/repos/owner/repo_name/contributors"
+        );
+    }
+
+    #[test]
+    fn builder_rejects_invalid_proxy() {
+        let result = DefaultClient::builder(ApiKey::new("key".into()))
+            .proxy("not a url")
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn builder_rejects_missing_ca_bundle() {
+        let result = DefaultClient::builder(ApiKey::new("key".into()))
+            .ca_bundle(test_file_path("doesnt_exist"))
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn loads_every_certificate_in_bundle() {
+        let certificates = load_ca_bundle(&test_file_path("example_ca_bundle.pem")).unwrap();
+        assert_eq!(certificates.len(), 2);
+    }
+
+    #[test]
+    fn builder_rejects_invalid_user_agent() {
+        let result = DefaultClient::builder(ApiKey::new("key".into()))
+            .user_agent("bus\nfactor")
+            .build();
+        assert!(matches!(result, Err(Error(ErrorKind::Headers(_), _))));
+    }
+
+    #[test]
+    fn builder_rejects_invalid_api_key() {
+        let result = DefaultClient::builder(ApiKey::new("key\r\n".into())).build();
+        assert!(matches!(result, Err(Error(ErrorKind::Headers(_), _))));
+    }
+
+    #[test]
+    fn api_key_is_not_exposed_by_debug() {
+        let client = client();
+        assert!(!format!("{:?}", client.headers).contains("key"));
+    }
+
+    fn mock_client(url: &str) -> DefaultClientBuilder {
+        DefaultClient::builder(ApiKey::new("key".into()))
+            .base_url(url)
+            .retry_policy(RetryPolicy {
+                max_retries: 1,
+                initial_backoff: Duration::ZERO,
+            })
+            .search_interval(Duration::ZERO)
+    }
+
+    const CONTRIBUTORS: &str = r#"[{"login": "user", "contributions": 3}]"#;
+
+    #[tokio::test]
+    async fn sends_configured_headers() {
+        let (url, requests) = serve(vec![MockResponse {
+            status: 200,
+            headers: vec![],
+            body: CONTRIBUTORS,
+        }])
+        .await;
+        let client = mock_client(&url)
+            .user_agent("custom-agent")
+            .build()
+            .unwrap();
+
+        client.list_contributors(&repo()).await.unwrap();
+
+        let request = requests.lock().unwrap()[0].to_lowercase();
+        assert!(request.starts_with("get /repos/owner/repo_name/contributors"));
+        assert!(request.contains("user-agent: custom-agent"));
+        assert!(request.contains("authorization: token key"));
+    }
+
+    #[tokio::test]
+    async fn requests_anonymous_contributors() {
+        let body = r#"[
+            {"login": "user", "contributions": 3, "type": "User"},
+            {"email": "jane@example.com", "name": "Jane", "contributions": 2, "type": "Anonymous"}
+        ]"#;
+        let (url, requests) = serve(vec![MockResponse {
+            status: 200,
+            headers: vec![],
+            body,
+        }])
+        .await;
+        let client = mock_client(&url)
+            .anonymous_contributors(true)
+            .build()
+            .unwrap();
+
+        let contributors = client.list_contributors(&repo()).await.unwrap();
+        assert_eq!(contributors[1].identity(), "Jane");
+        assert!(requests.lock().unwrap()[0]
+            .starts_with("GET /repos/owner/repo_name/contributors?anon=1 "));
+    }
+
+    #[tokio::test]
+    async fn anonymous_contributors_are_cached_apart() {
+        let response = || MockResponse {
+            status: 200,
+            headers: vec![],
+            body: CONTRIBUTORS,
+        };
+        let (url, requests) = serve(vec![response(), response()]).await;
+        let cache: Arc<dyn ResponseCache> = Arc::new(MemoryCache::default());
+        let ttl = Duration::from_secs(60);
+
+        for anonymous in [false, true, false, true] {
+            let client = mock_client(&url)
+                .anonymous_contributors(anonymous)
+                .build()
+                .unwrap();
+            let client = CachingClient::new(client, cache.clone(), ttl);
+            client.list_contributors(&repo()).await.unwrap();
+        }
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].starts_with("GET /repos/owner/repo_name/contributors?anon=1 "));
+    }
+
+    #[test]
+    fn anonymous_scope_is_kept_apart_from_the_url() {
+        let scope = |url: &str, anonymous: bool| {
+            mock_client(url)
+                .anonymous_contributors(anonymous)
+                .build()
+                .unwrap()
+                .cache_scope()
+        };
+        assert_ne!(
+            scope("https://ghe.corp/api?anon=1", false),
+            scope("https://ghe.corp/api", true)
+        );
+    }
+
+    #[test]
+    fn correct_commit_query_params() {
+        let window = Window {
+            since: Some("2021-01-01T00:00:00Z".parse().unwrap()),
+            until: None,
+        };
+        let query = DefaultClient::make_commit_query_params(window);
+        assert_eq!(
+            query,
+            vec![("since", "2021-01-01T00:00:00+00:00".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn lists_commits() {
+        let body = r#"[
+            {
+                "sha": "abc",
+                "commit": {"author": {"name": "Jane", "email": "jane@example.com", "date": "2021-03-01T10:00:00Z"}},
+                "author": {"login": "jane", "type": "User"}
+            },
+            {
+                "sha": "def",
+                "commit": {"author": {"name": "Anon", "email": "anon@example.com", "date": "2021-03-02T10:00:00Z"}},
+                "author": null
+            }
+        ]"#;
+        let (url, requests) = serve(vec![MockResponse {
+            status: 200,
+            headers: vec![],
+            body,
+        }])
+        .await;
+        let client = mock_client(&url).build().unwrap();
+
+        let commits = client
+            .list_commits(&repo(), Window::default())
+            .await
+            .unwrap();
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits[0].login.as_deref(), Some("jane"));
+        assert_eq!(commits[1].author().identity(), "Anon");
+        assert_eq!(
+            commits[1].timestamp,
+            "2021-03-02T10:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert!(requests.lock().unwrap()[0]
+            .starts_with("GET /repos/owner/repo_name/commits?per_page=100&page=1 "));
+    }
+
+    #[tokio::test]
+    async fn reads_named_repository() {
+        let (url, requests) = serve(vec![MockResponse {
+            status: 200,
+            headers: vec![],
+            body: r#"{"name": "repo_name", "owner": {"login": "owner"}, "stargazers_count": 5}"#,
+        }])
+        .await;
+        let client = mock_client(&url).build().unwrap();
+
+        let repository = client
+            .get_repository(&"owner/repo_name".parse().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(repository.name, "repo_name");
+        assert_eq!(repository.health.stargazers_count, 5);
+        assert!(requests.lock().unwrap()[0].starts_with("GET /repos/owner/repo_name "));
+    }
+
+    #[tokio::test]
+    async fn finds_last_commit_by_author() {
+        let body = r#"[
+            {
+                "sha": "abc",
+                "commit": {"author": {"name": "Jane", "email": "jane@example.com", "date": "2021-03-01T10:00:00Z"}},
+                "author": {"login": "jane", "type": "User"}
+            }
+        ]"#;
+        let (url, requests) = serve(vec![
+            MockResponse {
+                status: 200,
+                headers: vec![],
+                body,
+            },
+            MockResponse {
+                status: 200,
+                headers: vec![],
+                body: "[]",
+            },
+        ])
+        .await;
+        let client = mock_client(&url).build().unwrap();
+
+        let last_commit = client.last_commit(&repo(), "jane").await.unwrap();
+        assert_eq!(last_commit, Some("2021-03-01T10:00:00Z".parse().unwrap()));
+        assert!(requests.lock().unwrap()[0]
+            .starts_with("GET /repos/owner/repo_name/commits?author=jane&per_page=1 "));
+        assert_eq!(client.last_commit(&repo(), "nobody").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn lists_line_changes() {
+        let body = r#"[
+            {"author": {"login": "small"}, "weeks": [{"w": 1, "a": 1, "d": 2, "c": 5}]},
+            {"author": {"login": "big"}, "weeks": [{"a": 100, "d": 0}, {"a": 5, "d": 5}]},
+            {"author": null, "total": 1, "weeks": []}
+        ]"#;
+        let (url, _) = serve(vec![
+            // statistics are computed in the background on the first request
+            MockResponse {
+                status: 202,
+                headers: vec![],
+                body: "{}",
+            },
+            MockResponse {
+                status: 200,
+                headers: vec![],
+                body,
+            },
+        ])
+        .await;
+        let client = mock_client(&url).build().unwrap();
+
+        let contributors = client.list_line_changes(&repo()).await.unwrap();
+        let lines: Vec<_> = contributors
+            .iter()
+            .map(|c| (c.identity(), c.contributions))
+            .collect();
+        assert_eq!(lines, vec![("big", 110), ("small", 3)]);
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_statistics_that_are_never_ready() {
+        let not_ready = || MockResponse {
+            status: 202,
+            headers: vec![],
+            body: "{}",
+        };
+        let (url, requests) = serve((0..=STATS_POLLS).map(|_| not_ready()).collect()).await;
+        let client = mock_client(&url).build().unwrap();
+
+        match client.list_line_changes(&repo()).await {
+            Err(Error(ErrorKind::StatisticsNotReady(_), _)) => {}
+            other => panic!("expected statistics not to be ready, got {:?}", other),
+        }
+        assert_eq!(requests.lock().unwrap().len(), STATS_POLLS as usize + 1);
+    }
+
+    #[tokio::test]
+    async fn lists_reviewers_of_merged_pulls() {
+        let pulls = r#"[
+            {"number": 2, "user": {"login": "author"}, "merged_at": "2021-03-01T10:00:00Z"},
+            {"number": 1, "user": {"login": "author"}, "merged_at": null}
+        ]"#;
+        let reviews = r#"[
+            {"user": {"login": "reviewer"}, "state": "CHANGES_REQUESTED"},
+            {"user": {"login": "reviewer"}, "state": "APPROVED"}
+        ]"#;
+        let (url, requests) = serve(vec![
+            MockResponse {
+                status: 200,
+                headers: vec![],
+                body: pulls,
+            },
+            MockResponse {
+                status: 200,
+                headers: vec![],
+                body: reviews,
+            },
+        ])
+        .await;
+        let client = mock_client(&url).build().unwrap();
+
+        let reviewers = client.list_reviewers(&repo()).await.unwrap();
+        assert_eq!(reviewers.len(), 1);
+        assert_eq!(reviewers[0].identity(), "reviewer");
+        assert_eq!(reviewers[0].contributions, 1);
+
+        let requests = requests.lock().unwrap();
+        assert!(requests[0]
+            .starts_with("GET /repos/owner/repo_name/pulls?state=closed&per_page=100&page=1 "));
+        assert!(requests[1].starts_with("GET /repos/owner/repo_name/pulls/2/reviews "));
+    }
+
+    #[tokio::test]
+    async fn reads_codeowners_from_first_location() {
+        let (url, requests) = serve(vec![
+            MockResponse {
+                status: 404,
+                headers: vec![],
+                body: r#"{"message": "Not Found"}"#,
+            },
+            MockResponse {
+                status: 200,
+                headers: vec![],
+                // "* @alice\n", wrapped like the API does
+                body: r#"{"encoding": "base64", "content": "KiBAYWxp\nY2UK\n"}"#,
+            },
+        ])
+        .await;
+        let client = mock_client(&url).build().unwrap();
+
+        let codeowners = client.get_codeowners(&repo()).await.unwrap();
+        assert_eq!(codeowners.as_deref(), Some("* @alice\n"));
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].starts_with("GET /repos/owner/repo_name/contents/.github/CODEOWNERS "));
+        assert!(requests[1].starts_with("GET /repos/owner/repo_name/contents/CODEOWNERS "));
+    }
+
+    #[tokio::test]
+    async fn missing_codeowners_is_none() {
+        let not_found = || MockResponse {
+            status: 404,
+            headers: vec![],
+            body: r#"{"message": "Not Found"}"#,
+        };
+        let (url, _) = serve(vec![not_found(), not_found(), not_found()]).await;
+        let client = mock_client(&url).build().unwrap();
+
+        assert_eq!(client.get_codeowners(&repo()).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn reads_profile_and_organizations() {
+        let (url, requests) = serve(vec![
+            MockResponse {
+                status: 200,
+                headers: vec![],
+                body: r#"{"login": "octocat", "company": "@github", "followers": 20}"#,
+            },
+            MockResponse {
+                status: 200,
+                headers: vec![],
+                body: r#"[{"login": "github", "id": 1}, {"login": "rust-lang", "id": 2}]"#,
+            },
+        ])
+        .await;
+        let client = mock_client(&url).build().unwrap();
+
+        let profile = client.get_profile("octocat").await.unwrap();
+        assert_eq!(profile.company.as_deref(), Some("@github"));
+        let orgs = client.list_organizations("octocat").await.unwrap();
+        assert_eq!(orgs, vec!["github", "rust-lang"]);
+
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].starts_with("GET /users/octocat "));
+        assert!(requests[1].starts_with("GET /users/octocat/orgs "));
+    }
+
+    #[tokio::test]
+    async fn reads_latest_release_and_pulls_awaiting_review() {
+        let (url, requests) = serve(vec![
+            MockResponse {
+                status: 200,
+                headers: vec![],
+                body: r#"{"tag_name": "v1.0.0", "published_at": "2021-03-01T10:00:00Z"}"#,
+            },
+            MockResponse {
+                status: 200,
+                headers: vec![],
+                body: r#"{"total_count": 7, "items": []}"#,
+            },
+            MockResponse {
+                status: 404,
+                headers: vec![],
+                body: r#"{"message": "Not Found"}"#,
+            },
+        ])
+        .await;
+        let client = mock_client(&url).build().unwrap();
+
+        let release = client.latest_release(&repo()).await.unwrap();
+        assert_eq!(release, Some("2021-03-01T10:00:00Z".parse().unwrap()));
+        assert_eq!(
+            client.count_pulls_awaiting_review(&repo()).await.unwrap(),
+            7
+        );
+        assert_eq!(client.latest_release(&repo()).await.unwrap(), None);
+
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].starts_with("GET /repos/owner/repo_name/releases/latest "));
+        assert!(requests[1].starts_with("GET /search/issues?q=repo%3Aowner%2Frepo_name"));
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors() {
+        let (url, requests) = serve(vec![
+            MockResponse {
+                status: 502,
+                headers: vec![],
+                body: "",
+            },
+            MockResponse {
+                status: 200,
+                headers: vec![],
+                body: CONTRIBUTORS,
+            },
+        ])
+        .await;
+        let client = mock_client(&url).build().unwrap();
+
+        let contributors = client.list_contributors(&repo()).await.unwrap();
+        assert_eq!(contributors.len(), 1);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn retries_once_rate_limit_resets() {
+        let (url, requests) = serve(vec![
+            MockResponse {
+                status: 403,
+                headers: vec![("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "0")],
+                body: r#"{"message": "API rate limit exceeded"}"#,
+            },
+            MockResponse {
+                status: 200,
+                headers: vec![],
+                body: CONTRIBUTORS,
+            },
+        ])
+        .await;
+        let client = mock_client(&url).build().unwrap();
+
+        let contributors = client.list_contributors(&repo()).await.unwrap();
+        assert_eq!(contributors.len(), 1);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn reports_api_errors() {
+        let (url, _) = serve(vec![MockResponse {
+            status: 403,
+            headers: vec![],
+            body: r#"{"message": "Resource not accessible by integration"}"#,
+        }])
+        .await;
+        let client = mock_client(&url).build().unwrap();
+
+        match client.list_contributors(&repo()).await {
+            Err(Error(ErrorKind::Api(403, message), _)) => {
+                assert_eq!(message, "Resource not accessible by integration")
+            }
+            other => panic!("expected an API error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_rate_limits_once_retries_run_out() {
+        let limited = MockResponse {
+            status: 403,
+            headers: vec![("Retry-After", "0")],
+            body: r#"{"message": "You have exceeded a secondary rate limit"}"#,
+        };
+        let (url, requests) = serve(vec![limited.clone(), limited]).await;
+        let client = mock_client(&url).build().unwrap();
+
+        match client.count_pulls_awaiting_review(&repo()).await {
+            Err(Error(ErrorKind::RateLimited(message), _)) => {
+                assert_eq!(message, "You have exceeded a secondary rate limit")
+            }
+            other => panic!("expected a rate limit error, got {:?}", other),
+        }
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn spaces_out_searches() {
+        let search = MockResponse {
+            status: 200,
+            headers: vec![],
+            body: r#"{"total_count": 1}"#,
+        };
+        let (url, _) = serve(vec![search.clone(), search.clone(), search]).await;
+        let client = mock_client(&url)
+            .search_interval(Duration::from_millis(50))
+            .build()
+            .unwrap();
+
+        let start = std::time::Instant::now();
+        let repo = repo();
+        let counts = futures::future::try_join_all(
+            (0..3).map(|_| client.count_pulls_awaiting_review(&repo)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(counts, vec![1, 1, 1]);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn revalidates_cached_responses() {
+        let (url, requests) = serve(vec![
+            MockResponse {
+                status: 200,
+                headers: vec![("ETag", "\"v1\"")],
+                body: CONTRIBUTORS,
+            },
+            MockResponse {
+                status: 304,
+                headers: vec![],
+                body: "",
+            },
+        ])
+        .await;
+        let client = mock_client(&url)
+            .cache(Arc::new(MemoryCache::default()))
+            .build()
+            .unwrap();
+
+        let first = client.list_contributors(&repo()).await.unwrap();
+        let second = client.list_contributors(&repo()).await.unwrap();
+        assert_eq!(first, second);
+
+        let requests = requests.lock().unwrap();
+        assert!(!requests[0].to_lowercase().contains("if-none-match"));
+        assert!(requests[1].to_lowercase().contains("if-none-match: \"v1\""));
+    }
+
     #[test]
     fn correct_query_params() {
         let query = Query {
@@ -0,0 +1,227 @@
+use regex::Regex;
+
+use crate::{
+    errors::Error,
+    model::{Commit, OwnerFindings, SingleOwnerPath},
+};
+
+/// where Github looks for a CODEOWNERS file, in the order it looks
+pub(crate) const CODEOWNERS_PATHS: [&str; 3] =
+    [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// One line of a CODEOWNERS file
+#[derive(Debug, Clone)]
+struct Rule {
+    pattern: String,
+    matcher: Regex,
+    owners: Vec<String>,
+}
+
+/// The rules of a CODEOWNERS file, where the last rule matching a path decides its owners
+#[derive(Debug, Clone, Default)]
+pub struct CodeOwners {
+    rules: Vec<Rule>,
+}
+
+impl CodeOwners {
+    /// each line is a gitignore style pattern followed by owners, which are `@user`, `@org/team`
+    /// or an email
+    pub fn parse(contents: &str) -> Result<Self, Error> {
+        let mut rules = vec![];
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let mut words = line.split_whitespace();
+            let Some(pattern) = words.next() else {
+                continue;
+            };
+            rules.push(Rule {
+                pattern: pattern.to_owned(),
+                matcher: Regex::new(&pattern_regex(pattern))?,
+                owners: words.map(str::to_owned).collect(),
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    /// the owners of a path, empty if no rule matches or the matching rule lists nobody
+    pub fn owners(&self, path: &str) -> &[String] {
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matcher.is_match(path))
+            .map_or(&[], |rule| rule.owners.as_slice())
+    }
+
+    /// patterns that only one person or team owns
+    pub fn single_owner_paths(&self) -> Vec<SingleOwnerPath> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.owners.len() == 1)
+            .map(|rule| SingleOwnerPath {
+                pattern: rule.pattern.clone(),
+                owner: rule.owners[0].clone(),
+            })
+            .collect()
+    }
+
+    /// every individual owner, in order of first mention, leaving out teams
+    fn people(&self) -> Vec<&str> {
+        let mut people: Vec<&str> = vec![];
+        let owners = self.rules.iter().flat_map(|rule| &rule.owners);
+        for owner in owners.filter(|owner| !owner.contains('/')) {
+            if !people
+                .iter()
+                .any(|person| person.eq_ignore_ascii_case(owner))
+            {
+                people.push(owner);
+            }
+        }
+        people
+    }
+
+    /// compare the file with recent commits, finding single owner paths and listed people who
+    /// haven't committed
+    ///
+    /// teams can't be checked, since their members aren't known
+    pub fn findings(&self, recent_commits: &[Commit]) -> OwnerFindings {
+        let has_committed = |owner: &str| {
+            recent_commits
+                .iter()
+                .any(|commit| match owner.strip_prefix('@') {
+                    Some(login) => commit
+                        .login
+                        .as_deref()
+                        .is_some_and(|l| l.eq_ignore_ascii_case(login)),
+                    None => commit
+                        .email
+                        .as_deref()
+                        .is_some_and(|e| e.eq_ignore_ascii_case(owner)),
+                })
+        };
+
+        OwnerFindings {
+            single_owner_paths: self.single_owner_paths(),
+            inactive_owners: self
+                .people()
+                .into_iter()
+                .filter(|owner| !has_committed(owner))
+                .map(str::to_owned)
+                .collect(),
+        }
+    }
+}
+
+/// translate a gitignore style pattern into a regex over paths relative to the repository root
+///
+/// a pattern matching a directory also matches everything inside it, except that a trailing `/*`
+/// only matches the files directly in the directory, as Github documents for CODEOWNERS
+fn pattern_regex(pattern: &str) -> String {
+    // a slash at the start or in the middle anchors the pattern to the root
+    let trimmed = pattern.trim_end_matches('/');
+    let anchored = trimmed.contains('/');
+    let trimmed = trimmed.trim_start_matches('/');
+
+    let mut regex = String::from(if anchored { "^" } else { "^(?:.*/)?" });
+    let mut chars = trimmed.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    if !pattern.ends_with("/*") {
+        regex.push_str("(?:/.*)?");
+    }
+    regex.push('$');
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "
+        # default owners
+        *           @alice @org/core
+        /docs/      @bob
+        *.rs        @carol  # rust code
+        src/legacy/ carol@example.com
+        /build/logs/
+    ";
+
+    fn owners(codeowners: &CodeOwners, path: &str) -> Vec<String> {
+        codeowners.owners(path).to_vec()
+    }
+
+    #[test]
+    fn last_matching_rule_wins() {
+        let codeowners = CodeOwners::parse(EXAMPLE).unwrap();
+        assert_eq!(owners(&codeowners, "README.md"), ["@alice", "@org/core"]);
+        assert_eq!(owners(&codeowners, "docs/guide/intro.md"), ["@bob"]);
+        assert_eq!(owners(&codeowners, "src/main.rs"), ["@carol"]);
+        assert_eq!(
+            owners(&codeowners, "src/legacy/old.c"),
+            ["carol@example.com"]
+        );
+        assert!(owners(&codeowners, "build/logs/today.log").is_empty());
+    }
+
+    #[test]
+    fn translates_patterns() {
+        let matches =
+            |pattern: &str, path: &str| Regex::new(&pattern_regex(pattern)).unwrap().is_match(path);
+        assert!(matches("*", "any/path/at/all"));
+        assert!(matches("docs", "nested/docs/file"));
+        assert!(!matches("/docs", "nested/docs/file"));
+        assert!(matches("src/**/test.rs", "src/a/b/test.rs"));
+        assert!(!matches("src/*.rs", "src/a/b.rs"));
+        assert!(matches("docs/*", "docs/intro.md"));
+        assert!(!matches("docs/*", "docs/guide/intro.md"));
+        assert!(matches("docs/**", "docs/guide/intro.md"));
+        assert!(matches("file?.txt", "file1.txt"));
+        assert!(!matches("a.rs", "a_rs"));
+    }
+
+    #[test]
+    fn finds_single_owner_paths() {
+        let codeowners = CodeOwners::parse(EXAMPLE).unwrap();
+        let paths: Vec<_> = codeowners
+            .single_owner_paths()
+            .into_iter()
+            .map(|path| (path.pattern, path.owner))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("/docs/".into(), "@bob".into()),
+                ("*.rs".into(), "@carol".into()),
+                ("src/legacy/".into(), "carol@example.com".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_inactive_owners() {
+        let codeowners = CodeOwners::parse(EXAMPLE).unwrap();
+        let commit = |login: Option<&str>, email: &str| Commit {
+            login: login.map(str::to_owned),
+            name: None,
+            email: Some(email.into()),
+            account_type: None,
+            timestamp: Default::default(),
+        };
+        let recent = [
+            commit(Some("Alice"), "alice@example.com"),
+            commit(None, "carol@example.com"),
+        ];
+        assert_eq!(
+            codeowners.findings(&recent).inactive_owners,
+            vec!["@bob", "@carol"]
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use crate::{errors::Error, risk::RiskWeights};
+
+/// the config file looked for in the working directory
+pub const PROJECT_CONFIG: &str = "bus-factor.toml";
+
+/// Defaults for the command line options, read from TOML files such as:
+///
+/// ```toml
+/// project_count = 20
+/// languages = ["rust", "go"]
+/// repos = ["rust-lang/rust"]
+/// threshold = 0.8
+/// format = "json"
+/// exclude = ["ci-user"]
+/// cache_dir = "/tmp/bus-factor"
+///
+/// [risk_weights]
+/// archived = 0
+/// ```
+///
+/// Keys are named after the options they stand in for, and any option given on the command line
+/// takes precedence.
+#[derive(Debug, Clone, Default, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub project_count: Option<u32>,
+    #[serde(default)]
+    pub languages: Vec<String>,
+    /// `owner/name` of repositories to report on besides the search results
+    #[serde(default)]
+    pub repos: Vec<String>,
+    pub threshold: Option<f64>,
+    pub format: Option<String>,
+    pub sort_by: Option<String>,
+    pub include_bots: Option<bool>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub exclude_pattern: Vec<String>,
+    pub base_url: Option<String>,
+    pub concurrency: Option<usize>,
+    pub cache_dir: Option<PathBuf>,
+    pub risk_weights: Option<RiskWeights>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("couldn't read config file {}: {}", path.display(), e))?;
+        Self::from_toml(&contents)
+            .map_err(|e| format!("invalid config file {}: {}", path.display(), e).into())
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, Error> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    /// the user's config, then `./bus-factor.toml`, then `explicit`, each overriding the last
+    ///
+    /// The first two are skipped if they don't exist, while an explicit path must.
+    pub fn discover(explicit: Option<&Path>) -> Result<Self, Error> {
+        let mut config = Self::default();
+        let user = dirs::config_dir().map(|dir| dir.join("bus-factor").join("config.toml"));
+        for path in user.into_iter().chain([PathBuf::from(PROJECT_CONFIG)]) {
+            if path.is_file() {
+                debug!("reading config file {:?}", path);
+                config = config.merge(Self::load(&path)?);
+            }
+        }
+        if let Some(path) = explicit {
+            debug!("reading config file {:?}", path);
+            config = config.merge(Self::load(path)?);
+        }
+        Ok(config)
+    }
+
+    /// layer `other` over this config, lists replacing rather than extending each other
+    pub fn merge(self, other: Self) -> Self {
+        let list = |ours: Vec<String>, theirs: Vec<String>| match theirs.is_empty() {
+            true => ours,
+            false => theirs,
+        };
+        Self {
+            project_count: other.project_count.or(self.project_count),
+            languages: list(self.languages, other.languages),
+            repos: list(self.repos, other.repos),
+            threshold: other.threshold.or(self.threshold),
+            format: other.format.or(self.format),
+            sort_by: other.sort_by.or(self.sort_by),
+            include_bots: other.include_bots.or(self.include_bots),
+            exclude: list(self.exclude, other.exclude),
+            exclude_pattern: list(self.exclude_pattern, other.exclude_pattern),
+            base_url: other.base_url.or(self.base_url),
+            concurrency: other.concurrency.or(self.concurrency),
+            cache_dir: other.cache_dir.or(self.cache_dir),
+            risk_weights: other.risk_weights.or(self.risk_weights),
+        }
+    }
+
+    /// the config as command line arguments, to be parsed ahead of the real ones
+    ///
+    /// Flags are left out, since giving one twice would turn it off again.
+    pub(crate) fn to_args(&self) -> Vec<OsString> {
+        let mut args: Vec<(&str, String)> = vec![];
+        if let Some(count) = self.project_count {
+            args.push(("--project_count", count.to_string()));
+        }
+        args.extend(self.languages.iter().map(|l| ("--language", l.clone())));
+        args.extend(self.repos.iter().map(|repo| ("--repo", repo.clone())));
+        if let Some(threshold) = self.threshold {
+            args.push(("--threshold", threshold.to_string()));
+        }
+        if let Some(format) = &self.format {
+            args.push(("--format", format.clone()));
+        }
+        if let Some(sort_by) = &self.sort_by {
+            args.push(("--sort_by", sort_by.clone()));
+        }
+        args.extend(
+            self.exclude
+                .iter()
+                .map(|login| ("--exclude", login.clone())),
+        );
+        let patterns = self.exclude_pattern.iter();
+        args.extend(patterns.map(|pattern| ("--exclude_pattern", pattern.clone())));
+        if let Some(base_url) = &self.base_url {
+            args.push(("--base_url", base_url.clone()));
+        }
+        if let Some(concurrency) = self.concurrency {
+            args.push(("--concurrency", concurrency.to_string()));
+        }
+        if let Some(dir) = &self.cache_dir {
+            args.push(("--cache_dir", dir.display().to_string()));
+        }
+        if let Some(weights) = self.risk_weights {
+            let RiskWeights {
+                concentration,
+                inactivity,
+                criticality,
+                archived,
+            } = weights;
+            let weights = format!(
+                "concentration={},inactivity={},criticality={},archived={}",
+                concentration, inactivity, criticality, archived
+            );
+            args.push(("--risk_weights", weights));
+        }
+
+        args.into_iter()
+            .flat_map(|(name, value)| [OsString::from(name), OsString::from(value)])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_config() {
+        let config = Config::from_toml(
+            r#"
+            project_count = 20
+            languages = ["rust", "go"]
+            repos = ["rust-lang/rust"]
+            threshold = 0.8
+            format = "json"
+            include_bots = true
+            exclude = ["ci-user"]
+            base_url = "https://ghe.example/api/v3"
+            concurrency = 4
+            cache_dir = "/tmp/bus-factor"
+
+            [risk_weights]
+            archived = 0
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.project_count, Some(20));
+        assert_eq!(config.languages, ["rust", "go"]);
+        assert_eq!(config.repos, ["rust-lang/rust"]);
+        assert_eq!(config.threshold, Some(0.8));
+        assert_eq!(config.include_bots, Some(true));
+        assert_eq!(config.concurrency, Some(4));
+        assert_eq!(config.risk_weights.unwrap().archived, 0.0);
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let error = Config::from_toml("project_count = 5\nlanguage = \"rust\"\n").unwrap_err();
+        let message = error.to_string();
+        assert!(message.contains("unknown field `language`"), "{}", message);
+        assert!(message.contains("languages"), "{}", message);
+
+        assert!(Config::from_toml("[risk_weights]\nstars = 1\n").is_err());
+    }
+
+    #[test]
+    fn names_the_invalid_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bus-factor.toml");
+        std::fs::write(&path, "thresold = 0.5\n").unwrap();
+        let message = Config::load(&path).unwrap_err().to_string();
+        assert!(message.contains("bus-factor.toml"), "{}", message);
+        assert!(message.contains("thresold"), "{}", message);
+
+        assert!(Config::load(&dir.path().join("missing.toml")).is_err());
+    }
+
+    #[test]
+    fn later_layers_take_precedence() {
+        let user = Config {
+            project_count: Some(10),
+            languages: vec!["rust".into()],
+            exclude: vec!["bot".into()],
+            ..Default::default()
+        };
+        let project = Config {
+            languages: vec!["go".into()],
+            format: Some("json".into()),
+            include_bots: Some(false),
+            ..Default::default()
+        };
+        let config = user.merge(Config {
+            include_bots: Some(true),
+            ..Default::default()
+        });
+        assert_eq!(config.include_bots, Some(true));
+        let config = config.merge(project);
+        assert_eq!(config.include_bots, Some(false));
+        assert_eq!(config.project_count, Some(10));
+        assert_eq!(config.languages, ["go"]);
+        assert_eq!(config.exclude, ["bot"]);
+        assert_eq!(config.format.as_deref(), Some("json"));
+    }
+
+    #[test]
+    fn converts_to_args() {
+        let config = Config {
+            project_count: Some(10),
+            languages: vec!["rust".into(), "go".into()],
+            include_bots: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.to_args(),
+            [
+                "--project_count",
+                "10",
+                "--language",
+                "rust",
+                "--language",
+                "go"
+            ]
+        );
+    }
+}
@@ -0,0 +1,396 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::client::DEFAULT_USER_AGENT;
+use crate::errors::Error;
+use crate::model::{Owner, Repository};
+
+/// the crates.io API, which any registry used with `--registry_url` has to mirror
+pub const DEFAULT_REGISTRY_URL: &str = "https://crates.io/api/v1";
+/// the crates.io crawler policy asks for at most one request a second
+pub const DEFAULT_REGISTRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A dependency listed in a `Cargo.toml`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Dependency {
+    /// the crate's name in the registry, which differs from its key when renamed with `package`
+    pub name: String,
+    /// the repository the crate is taken from instead of the registry
+    pub git: Option<String>,
+    /// whether it is a `path` dependency, which has no repository of its own
+    pub local: bool,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum Spec {
+    Version(#[allow(dead_code)] String),
+    Detailed {
+        package: Option<String>,
+        git: Option<String>,
+        path: Option<String>,
+        version: Option<String>,
+    },
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct Tables {
+    dependencies: BTreeMap<String, Spec>,
+    dev_dependencies: BTreeMap<String, Spec>,
+    build_dependencies: BTreeMap<String, Spec>,
+}
+
+#[derive(Default, serde::Deserialize)]
+#[serde(default)]
+struct Manifest {
+    #[serde(flatten)]
+    tables: Tables,
+    target: BTreeMap<String, Tables>,
+    workspace: Option<Tables>,
+}
+
+/// every dependency in the manifest at `path`, including dev, build, target specific and
+/// workspace dependencies, sorted by name
+pub fn load_manifest(path: &Path) -> Result<Vec<Dependency>, Error> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("couldn't read manifest {}: {}", path.display(), e))?;
+    parse_manifest(&contents)
+        .map_err(|e| format!("invalid manifest {}: {}", path.display(), e).into())
+}
+
+pub fn parse_manifest(contents: &str) -> Result<Vec<Dependency>, Error> {
+    let manifest: Manifest = toml::from_str(contents)?;
+    let tables = std::iter::once(manifest.tables)
+        .chain(manifest.target.into_values())
+        .chain(manifest.workspace);
+
+    let mut dependencies: Vec<_> = tables
+        .flat_map(|t| [t.dependencies, t.dev_dependencies, t.build_dependencies])
+        .flatten()
+        .map(|(key, spec)| match spec {
+            Spec::Version(_) => Dependency {
+                name: key,
+                git: None,
+                local: false,
+            },
+            Spec::Detailed {
+                package,
+                git,
+                path,
+                version,
+            } => Dependency {
+                name: package.unwrap_or(key),
+                // a path dependency with a version is published, and resolved like any other
+                local: path.is_some() && version.is_none() && git.is_none(),
+                git,
+            },
+        })
+        .collect();
+    dependencies.sort();
+    dependencies.dedup_by(|a, b| a.name == b.name);
+    Ok(dependencies)
+}
+
+/// the dependencies of `new` that `old` doesn't have
+pub fn added(old: &[Dependency], new: &[Dependency]) -> Vec<Dependency> {
+    let old: HashSet<_> = old.iter().map(|dependency| &dependency.name).collect();
+    new.iter()
+        .filter(|dependency| !old.contains(&dependency.name))
+        .cloned()
+        .collect()
+}
+
+/// the repository at a Github URL such as `https://github.com/owner/name.git`
+pub fn github_repository(url: &str) -> Option<Repository> {
+    let url = url.trim().trim_end_matches('/');
+    let path = ["https://", "http://", "git://", "ssh://git@", "git@"]
+        .iter()
+        .find_map(|scheme| url.strip_prefix(scheme))?;
+    let path = path
+        .strip_prefix("github.com/")
+        .or_else(|| path.strip_prefix("www.github.com/"))
+        .or_else(|| path.strip_prefix("github.com:"))?;
+    let mut segments = path.split('/');
+    let (owner, name) = (segments.next()?, segments.next()?);
+    let name = name.trim_end_matches(".git");
+    if owner.is_empty() || name.is_empty() {
+        return None;
+    }
+    Some(Repository {
+        name: name.into(),
+        owner: Owner {
+            login: owner.into(),
+        },
+        ..Default::default()
+    })
+}
+
+#[derive(serde::Deserialize)]
+struct CrateResponse {
+    #[serde(rename = "crate")]
+    krate: Crate,
+}
+
+#[derive(serde::Deserialize)]
+struct Crate {
+    repository: Option<String>,
+}
+
+/// Looks up where crates are developed, using the repository in their registry metadata
+#[derive(Debug, Clone)]
+pub struct Registry {
+    base_url: String,
+    user_agent: String,
+    http: reqwest::Client,
+    interval: Duration,
+    last_request: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new(DEFAULT_REGISTRY_URL, DEFAULT_USER_AGENT)
+    }
+}
+
+impl Registry {
+    /// crates.io rejects requests without a user agent
+    pub fn new(base_url: impl Into<String>, user_agent: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').into(),
+            user_agent: user_agent.into(),
+            http: reqwest::Client::new(),
+            interval: DEFAULT_REGISTRY_INTERVAL,
+            last_request: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// the time between requests to the registry, [`DEFAULT_REGISTRY_INTERVAL`] by default
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// the Github repository of each dependency, each only once
+    ///
+    /// Dependencies without one, like local crates or those hosted elsewhere, are logged and
+    /// left out. They are looked up one at a time, as the registry asks of crawlers.
+    pub async fn repositories(
+        &self,
+        dependencies: &[Dependency],
+    ) -> Result<Vec<Repository>, Error> {
+        let mut seen = HashSet::new();
+        let mut repos = vec![];
+        for dependency in dependencies {
+            if let Some(repo) = self.repository(dependency).await? {
+                if seen.insert((repo.owner.login.clone(), repo.name.clone())) {
+                    repos.push(repo);
+                }
+            }
+        }
+        Ok(repos)
+    }
+
+    async fn repository(&self, dependency: &Dependency) -> Result<Option<Repository>, Error> {
+        if dependency.local {
+            debug!("skipping local dependency {}", dependency.name);
+            return Ok(None);
+        }
+        let url = match &dependency.git {
+            Some(url) => url.clone(),
+            None => match self.crate_repository(&dependency.name).await? {
+                Some(url) => url,
+                None => {
+                    warn!(
+                        "{} doesn't name its repository, skipping it",
+                        dependency.name
+                    );
+                    return Ok(None);
+                }
+            },
+        };
+        let repo = github_repository(&url);
+        if repo.is_none() {
+            warn!(
+                "{} isn't hosted on Github ({}), skipping it",
+                dependency.name, url
+            );
+        }
+        Ok(repo)
+    }
+
+    async fn crate_repository(&self, name: &str) -> Result<Option<String>, Error> {
+        let url = format!("{}/crates/{}", self.base_url, name);
+        {
+            let mut last_request = self.last_request.lock().await;
+            if let Some(last_request) = *last_request {
+                tokio::time::sleep_until(last_request + self.interval).await;
+            }
+            *last_request = Some(Instant::now());
+        }
+        debug!("creating request for url: {}", url);
+        let response = self
+            .http
+            .get(&url)
+            .header(reqwest::header::USER_AGENT, &self.user_agent)
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            warn!("{} isn't in the registry, skipping it", name);
+            return Ok(None);
+        }
+        let response: CrateResponse = response.error_for_status()?.json().await?;
+        Ok(response.krate.repository)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{serve, MockResponse};
+
+    const MANIFEST: &str = r#"
+        [package]
+        name = "example"
+
+        [dependencies]
+        serde = "1"
+        json = { package = "serde_json", version = "1" }
+        helper = { path = "../helper" }
+        published = { path = "../published", version = "0.2" }
+        fork = { git = "https://github.com/someone/fork.git", branch = "main" }
+
+        [dev-dependencies]
+        serde = { version = "1", features = ["derive"] }
+
+        [target.'cfg(unix)'.build-dependencies]
+        cc = "1"
+    "#;
+
+    fn dependency(name: &str) -> Dependency {
+        Dependency {
+            name: name.into(),
+            git: None,
+            local: false,
+        }
+    }
+
+    #[test]
+    fn parses_every_table() {
+        let dependencies = parse_manifest(MANIFEST).unwrap();
+        let names: Vec<_> = dependencies.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["cc", "fork", "helper", "published", "serde", "serde_json"]
+        );
+        assert!(dependencies[2].local);
+        assert!(!dependencies[3].local);
+        assert_eq!(
+            dependencies[1].git.as_deref(),
+            Some("https://github.com/someone/fork.git")
+        );
+
+        let workspace = "[workspace.dependencies]\ntokio = \"1\"\n";
+        assert_eq!(parse_manifest(workspace).unwrap(), [dependency("tokio")]);
+        assert!(parse_manifest("[dependencies]\nserde = 1\n").is_err());
+    }
+
+    #[test]
+    fn finds_added_dependencies() {
+        let old = [dependency("serde"), dependency("log")];
+        let new = [dependency("log"), dependency("regex"), dependency("serde")];
+        assert_eq!(added(&old, &new), [dependency("regex")]);
+    }
+
+    #[test]
+    fn parses_github_urls() {
+        let repo = |url| github_repository(url).map(|r| format!("{}/{}", r.owner.login, r.name));
+        assert_eq!(
+            repo("https://github.com/serde-rs/json").as_deref(),
+            Some("serde-rs/json")
+        );
+        assert_eq!(repo("https://github.com/o/n.git/").as_deref(), Some("o/n"));
+        assert_eq!(
+            repo("https://github.com/o/n/tree/main/sub").as_deref(),
+            Some("o/n")
+        );
+        assert_eq!(repo("git@github.com:o/n.git").as_deref(), Some("o/n"));
+        assert_eq!(repo("https://gitlab.com/o/n"), None);
+        assert_eq!(repo("https://github.com/o"), None);
+    }
+
+    #[tokio::test]
+    async fn looks_up_repositories() {
+        let (url, requests) = serve(vec![
+            MockResponse {
+                status: 200,
+                headers: vec![("Content-Type", "application/json")],
+                body: r#"{"crate": {"repository": "https://github.com/serde-rs/json"}}"#,
+            },
+            MockResponse {
+                status: 404,
+                headers: vec![],
+                body: "",
+            },
+        ])
+        .await;
+        let registry = Registry::new(url, "test-agent").interval(Duration::ZERO);
+        let dependencies = [
+            dependency("serde_json"),
+            Dependency {
+                name: "helper".into(),
+                git: None,
+                local: true,
+            },
+            Dependency {
+                name: "fork".into(),
+                git: Some("https://github.com/someone/fork".into()),
+                local: false,
+            },
+        ];
+        let repos = registry.repositories(&dependencies).await.unwrap();
+        let names: Vec<_> = repos.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["json", "fork"]);
+
+        let repos = registry
+            .repositories(&[dependency("missing")])
+            .await
+            .unwrap();
+        assert!(repos.is_empty());
+
+        let requests = requests.lock().unwrap();
+        assert!(
+            requests[0].starts_with("GET /crates/serde_json "),
+            "{}",
+            requests[0]
+        );
+        assert!(requests[0]
+            .to_lowercase()
+            .contains("user-agent: test-agent"));
+    }
+
+    #[tokio::test]
+    async fn spaces_out_registry_requests() {
+        let found = MockResponse {
+            status: 200,
+            headers: vec![("Content-Type", "application/json")],
+            body: r#"{"crate": {"repository": "https://github.com/serde-rs/json"}}"#,
+        };
+        let (url, requests) = serve(vec![found.clone(), found]).await;
+        let registry = Registry::new(url, "test-agent").interval(Duration::from_millis(50));
+
+        let start = std::time::Instant::now();
+        let repos = registry
+            .repositories(&[dependency("serde_json"), dependency("serde_json_alias")])
+            .await
+            .unwrap();
+        assert_eq!(repos.len(), 1, "the same repository is only reported once");
+        assert_eq!(requests.lock().unwrap().len(), 2);
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+}
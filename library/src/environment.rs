@@ -1,22 +1,164 @@
 use microtype::SecretMicrotype;
 
 use std::fs::read_to_string;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::str::FromStr;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
 
-use crate::errors::Error;
+use crate::client::DEFAULT_BASE_URL;
+use crate::errors::{Error, ErrorKind};
 use crate::model::ApiKey;
 
 pub const KEY_ENV_VAR_NAME: &str = "BUS_FACTOR_AUTH";
 
+/// the variables the gh CLI and Github Actions use for github.com, in the order gh checks them
+const GITHUB_ENV_VAR_NAMES: [&str; 2] = ["GH_TOKEN", "GITHUB_TOKEN"];
+
+/// the variables gh uses for any other host, so a github.com token isn't sent to them
+const ENTERPRISE_ENV_VAR_NAMES: [&str; 2] = ["GH_ENTERPRISE_TOKEN", "GITHUB_ENTERPRISE_TOKEN"];
+
+/// the host of the public API, the only one given tokens that don't name a host
+const GITHUB_HOST: &str = "github.com";
+
+/// the service a token is stored under in the Secret Service keyring, see [`AuthSource::Keyring`]
+const KEYRING_SERVICE: &str = "bus-factor";
+
+/// how long to wait for `secret-tool`, which can hang on a locked keyring or a missing D-Bus session
+const KEYRING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A place to look for the API key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthSource {
+    /// the `BUS_FACTOR_AUTH` env var
+    Env,
+    /// the file given with `--key_file`
+    KeyFile,
+    /// the `GH_TOKEN` or `GITHUB_TOKEN` env vars for github.com, `GH_ENTERPRISE_TOKEN` or
+    /// `GITHUB_ENTERPRISE_TOKEN` for other hosts
+    GithubEnv,
+    /// the `hosts.yml` the gh CLI writes when it keeps its token in a file
+    Gh,
+    /// the password of the API's host in `~/.netrc`, or `$NETRC`, or its `default` for github.com
+    Netrc,
+    /// the Secret Service keyring, via `secret-tool`, under `service bus-factor host <host>` or
+    /// where the gh CLI stores its token
+    Keyring,
+}
+
+impl AuthSource {
+    /// the values accepted on the command line, in the order they are tried
+    pub const VARIANTS: &'static [&'static str] =
+        &["env", "key_file", "github_env", "gh", "netrc", "keyring"];
+
+    const ALL: [Self; 6] = [
+        Self::Env,
+        Self::KeyFile,
+        Self::GithubEnv,
+        Self::Gh,
+        Self::Netrc,
+        Self::Keyring,
+    ];
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Env => "the BUS_FACTOR_AUTH env var",
+            Self::KeyFile => "the key file",
+            Self::GithubEnv => {
+                "the GH_TOKEN or GITHUB_TOKEN env var (GH_ENTERPRISE_TOKEN for others)"
+            }
+            Self::Gh => "the gh CLI's hosts.yml",
+            Self::Netrc => "the netrc file",
+            Self::Keyring => "the Secret Service keyring",
+        }
+    }
+}
+
+impl FromStr for AuthSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "env" => Ok(Self::Env),
+            "key_file" => Ok(Self::KeyFile),
+            "github_env" => Ok(Self::GithubEnv),
+            "gh" => Ok(Self::Gh),
+            "netrc" => Ok(Self::Netrc),
+            "keyring" => Ok(Self::Keyring),
+            _ => Err(format!(
+                "unknown auth source {:?}, expected one of: {}",
+                s,
+                Self::VARIANTS.join(", ")
+            )),
+        }
+    }
+}
+
+/// Where to look for the API key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthOptions {
+    pub key_file: Option<PathBuf>,
+    /// the API the key is for, which picks the entry in the gh CLI's config, netrc and keyring
+    pub base_url: String,
+    /// only look here, rather than trying every source in order
+    pub source: Option<AuthSource>,
+}
+
+impl Default for AuthOptions {
+    fn default() -> Self {
+        Self {
+            key_file: None,
+            base_url: DEFAULT_BASE_URL.into(),
+            source: None,
+        }
+    }
+}
+
+/// the first API key found in any source, with the key file given
 pub fn get_api_key(key_file: &Option<PathBuf>) -> Result<ApiKey, Error> {
-    if let Some(key) = api_key_from_env() {
-        info!("using API key from env var");
-        Ok(key)
-    } else if let Some(key) = api_key_from_file(key_file) {
-        info!("using API key from file: {:?}", key_file);
-        Ok(key)
-    } else {
-        bail!(crate::errors::ErrorKind::MissingAuth)
+    get_api_key_with(&AuthOptions {
+        key_file: key_file.clone(),
+        ..Default::default()
+    })
+}
+
+/// the first API key found, trying each source in the order of [`AuthSource::VARIANTS`]
+pub fn get_api_key_with(options: &AuthOptions) -> Result<ApiKey, Error> {
+    let host = api_host(&options.base_url);
+    let sources = match options.source {
+        Some(source) => vec![source],
+        None => AuthSource::ALL.to_vec(),
+    };
+    for source in sources {
+        let key = match source {
+            AuthSource::Env => api_key_from_env(),
+            AuthSource::KeyFile => api_key_from_file(&options.key_file),
+            AuthSource::GithubEnv => api_key_from_github_env(&host),
+            AuthSource::Gh => api_key_from_gh(&host),
+            AuthSource::Netrc => api_key_from_netrc(&host),
+            AuthSource::Keyring => api_key_from_keyring(&host),
+        };
+        match key {
+            Some(key) => {
+                info!("using API key from {}", source.describe());
+                return Ok(key);
+            }
+            None => debug!("no API key in {}", source.describe()),
+        }
+    }
+    bail!(ErrorKind::MissingAuth)
+}
+
+/// the host credentials are stored under, `github.com` for the public API
+fn api_host(base_url: &str) -> String {
+    let host = reqwest::Url::parse(base_url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_owned))
+        .unwrap_or_default();
+    match host.as_str() {
+        "api.github.com" => GITHUB_HOST.into(),
+        _ => host,
     }
 }
 
@@ -35,6 +177,131 @@ fn api_key_from_file(key_file: &Option<PathBuf>) -> Option<ApiKey> {
     }
 }
 
+fn api_key_from_github_env(host: &str) -> Option<ApiKey> {
+    token_from_github_env(host, |name| std::env::var(name).ok()).map(ApiKey::new)
+}
+
+/// the first non-empty variable gh would read for `host`, looked up with `var`
+fn token_from_github_env(host: &str, var: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let names = match host {
+        GITHUB_HOST => GITHUB_ENV_VAR_NAMES,
+        _ => ENTERPRISE_ENV_VAR_NAMES,
+    };
+    names
+        .iter()
+        .filter_map(|name| var(name))
+        .find(|token| !token.trim().is_empty())
+}
+
+fn api_key_from_gh(host: &str) -> Option<ApiKey> {
+    let dir = match std::env::var_os("GH_CONFIG_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) => Path::new(&dir).join("gh"),
+            None => dirs::home_dir()?.join(".config").join("gh"),
+        },
+    };
+    let hosts = read_to_string(dir.join("hosts.yml")).ok()?;
+    token_from_gh_hosts(&hosts, host).map(ApiKey::new)
+}
+
+/// the `oauth_token` of `host` in the gh CLI's `hosts.yml`
+///
+/// Only the little YAML gh writes is understood: a map from hosts to their settings, in which
+/// the active user's token is the least indented.
+fn token_from_gh_hosts(hosts: &str, host: &str) -> Option<String> {
+    let indent = |line: &str| line.len() - line.trim_start().len();
+    let mut lines = hosts.lines().skip_while(|line| {
+        indent(line) > 0 || line.trim_end().trim_end_matches(':').trim_matches('"') != host
+    });
+    lines.next()?;
+    lines
+        .take_while(|line| line.trim().is_empty() || indent(line) > 0)
+        .filter_map(|line| {
+            let token = line.trim().strip_prefix("oauth_token:")?;
+            Some((indent(line), token.trim().trim_matches('"').to_owned()))
+        })
+        .filter(|(_, token)| !token.is_empty())
+        .min_by_key(|(indent, _)| *indent)
+        .map(|(_, token)| token)
+}
+
+fn api_key_from_netrc(host: &str) -> Option<ApiKey> {
+    let path = match std::env::var_os("NETRC") {
+        Some(path) => PathBuf::from(path),
+        None => dirs::home_dir()?.join(".netrc"),
+    };
+    let netrc = read_to_string(path).ok()?;
+    token_from_netrc(&netrc, host).map(ApiKey::new)
+}
+
+/// the password for `host`, or its `api.` subdomain, falling back to the `default` entry for
+/// github.com only, so that it isn't sent to other hosts
+fn token_from_netrc(netrc: &str, host: &str) -> Option<String> {
+    let api_host = format!("api.{}", host);
+    let mut tokens = netrc.split_whitespace();
+    let (mut machine, mut found, mut default) = (None, None, None);
+    while let Some(token) = tokens.next() {
+        match token {
+            "machine" => machine = tokens.next(),
+            "default" => machine = Some(""),
+            "login" | "account" => {
+                tokens.next();
+            }
+            "password" => {
+                let password = tokens.next().map(str::to_owned);
+                match machine {
+                    Some(m) if m == host || m == api_host => found = found.or(password),
+                    Some("") => default = default.or(password),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    found.or(default.filter(|_| host == GITHUB_HOST))
+}
+
+fn api_key_from_keyring(host: &str) -> Option<ApiKey> {
+    let gh_service = format!("gh:{}", host);
+    let lookups = [
+        vec!["service", KEYRING_SERVICE, "host", host],
+        vec!["service", gh_service.as_str(), "username", ""],
+    ];
+    lookups.iter().find_map(|attributes| {
+        let mut command = Command::new("secret-tool");
+        command.arg("lookup").args(attributes);
+        let output = output_within(&mut command, KEYRING_TIMEOUT)?;
+        let token = String::from_utf8(output.stdout).ok()?;
+        let token = token.trim();
+        (output.status.success() && !token.is_empty()).then(|| ApiKey::new(token.to_owned()))
+    })
+}
+
+/// run a command, killing it if it hasn't finished within the timeout
+fn output_within(command: &mut Command, timeout: Duration) -> Option<Output> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| debug!("couldn't run {:?}: {}", command, e))
+        .ok()?;
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(_)) => return child.wait_with_output().ok(),
+            Ok(None) if Instant::now() < deadline => sleep(Duration::from_millis(10)),
+            _ => {
+                debug!("gave up on {:?} after {:?}", command, timeout);
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use secrecy::ExposeSecret;
@@ -52,7 +319,7 @@ mod tests {
 
     #[test]
     fn api_key_from_file_none() {
-        assert!(matches!(api_key_from_file(&None), None));
+        assert!(api_key_from_file(&None).is_none());
     }
 
     #[test]
@@ -69,5 +336,131 @@ mod tests {
         assert!(key.is_none());
     }
 
+    #[test]
+    fn forced_source_is_the_only_one_tried() {
+        let options = AuthOptions {
+            key_file: Some(test_file_path("example_key_file")),
+            source: Some(AuthSource::KeyFile),
+            ..Default::default()
+        };
+        let key = get_api_key_with(&options).unwrap();
+        assert_eq!(key.expose_secret(), "example key");
+
+        let options = AuthOptions {
+            source: Some(AuthSource::KeyFile),
+            ..Default::default()
+        };
+        assert!(get_api_key_with(&options).is_err());
+    }
+
+    #[test]
+    fn gives_up_on_slow_commands() {
+        let output = output_within(Command::new("echo").arg("token"), KEYRING_TIMEOUT).unwrap();
+        assert_eq!(output.stdout, b"token\n");
+
+        let started = Instant::now();
+        let output = output_within(Command::new("sleep").arg("10"), Duration::from_millis(50));
+        assert!(output.is_none());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
 
+    #[test]
+    fn parses_auth_sources() {
+        for name in AuthSource::VARIANTS {
+            assert!(name.parse::<AuthSource>().is_ok(), "{}", name);
+        }
+        assert_eq!("gh".parse(), Ok(AuthSource::Gh));
+        assert!("vault".parse::<AuthSource>().is_err());
+    }
+
+    #[test]
+    fn finds_api_host() {
+        assert_eq!(api_host(DEFAULT_BASE_URL), "github.com");
+        assert_eq!(api_host("https://ghe.corp/api/v3"), "ghe.corp");
+    }
+
+    #[test]
+    fn reads_gh_hosts() {
+        let hosts = "\
+github.com:
+    users:
+        other:
+            oauth_token: gho_other
+    oauth_token: gho_active
+    user: someone
+    git_protocol: https
+ghe.corp:
+    oauth_token: \"gho_corp\"
+";
+        assert_eq!(
+            token_from_gh_hosts(hosts, "github.com").as_deref(),
+            Some("gho_active")
+        );
+        assert_eq!(
+            token_from_gh_hosts(hosts, "ghe.corp").as_deref(),
+            Some("gho_corp")
+        );
+        assert_eq!(token_from_gh_hosts(hosts, "gitlab.com"), None);
+
+        // newer versions of gh keep the token in the keyring instead
+        let hosts = "github.com:\n    user: someone\n    git_protocol: https\n";
+        assert_eq!(token_from_gh_hosts(hosts, "github.com"), None);
+    }
+
+    #[test]
+    fn reads_netrc() {
+        let netrc = "\
+machine gitlab.com login me password gitlab
+machine api.github.com
+    login me
+    password github
+default login anonymous password fallback
+";
+        assert_eq!(
+            token_from_netrc(netrc, "github.com").as_deref(),
+            Some("github")
+        );
+        assert_eq!(token_from_netrc(netrc, "ghe.corp"), None);
+        assert_eq!(
+            token_from_netrc("default password fallback", "github.com").as_deref(),
+            Some("fallback")
+        );
+        assert_eq!(
+            token_from_netrc("machine ghe.corp password corp\n", "ghe.corp").as_deref(),
+            Some("corp")
+        );
+        assert_eq!(token_from_netrc("machine a password b", "github.com"), None);
+    }
+
+    #[test]
+    fn reads_github_env() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                let value = vars.iter().find(|(var, _)| *var == name)?.1;
+                Some(value.to_owned())
+            }
+        };
+        let both = env(&[("GITHUB_TOKEN", "actions"), ("GH_TOKEN", "gh")]);
+        assert_eq!(
+            token_from_github_env("github.com", both).as_deref(),
+            Some("gh")
+        );
+        assert_eq!(token_from_github_env("ghe.corp", both), None);
+
+        let blank = env(&[("GH_TOKEN", " "), ("GITHUB_TOKEN", "actions")]);
+        assert_eq!(
+            token_from_github_env("github.com", blank).as_deref(),
+            Some("actions")
+        );
+
+        let enterprise = env(&[("GH_ENTERPRISE_TOKEN", "corp"), ("GITHUB_TOKEN", "actions")]);
+        assert_eq!(
+            token_from_github_env("ghe.corp", enterprise).as_deref(),
+            Some("corp")
+        );
+        assert_eq!(
+            token_from_github_env("github.com", enterprise).as_deref(),
+            Some("actions")
+        );
+    }
 }
@@ -6,11 +6,39 @@ error_chain! {
         Io(std::io::Error);
         Request(reqwest::Error);
         Headers(InvalidHeaderValue);
+        Git(git2::Error);
+        Json(serde_json::Error);
+        Regex(regex::Error);
+        Toml(toml::de::Error);
     }
 
     errors {
         MissingAuth {
             description("no Github auth token provided")
         }
+        Unsupported(operation: String) {
+            description("operation not supported by this client")
+            display("{} is not supported by this client", operation)
+        }
+        NotFound(url: String) {
+            description("the API has nothing at this URL")
+            display("not found: {}", url)
+        }
+        Api(status: u16, message: String) {
+            description("the API answered with an error")
+            display("the API answered {}: {}", status, message)
+        }
+        RateLimited(message: String) {
+            description("the API rate limit is used up")
+            display("rate limited by the API, try again later: {}", message)
+        }
+        StatisticsNotReady(url: String) {
+            description("the API is still computing the statistics")
+            display("statistics not ready, Github is still computing them: {}", url)
+        }
+        MissingFixture(path: String) {
+            description("no recorded fixture for request")
+            display("no recorded fixture at {}", path)
+        }
     }
 }
@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+
+use regex::Regex;
+
+use crate::{errors::Error, model::Contributor};
+
+/// Decides which accounts are automation rather than people, so they don't count towards the bus
+/// factor
+///
+/// Bots are detected from the Github account type and the `[bot]` login suffix unless disabled,
+/// and any identity on the deny list or matching one of the patterns is excluded as well
+#[derive(Debug, Clone)]
+pub struct BotFilter {
+    detect_bots: bool,
+    deny: HashSet<String>,
+    patterns: Vec<Regex>,
+}
+
+impl Default for BotFilter {
+    fn default() -> Self {
+        Self {
+            detect_bots: true,
+            deny: HashSet::new(),
+            patterns: vec![],
+        }
+    }
+}
+
+impl BotFilter {
+    /// a filter which keeps every contributor
+    pub fn none() -> Self {
+        Self {
+            detect_bots: false,
+            ..Default::default()
+        }
+    }
+
+    pub fn detect_bots(mut self, enable: bool) -> Self {
+        self.detect_bots = enable;
+        self
+    }
+
+    /// exclude this login (or name/email for anonymous contributors), ignoring case
+    pub fn deny(mut self, identity: impl AsRef<str>) -> Self {
+        self.deny.insert(identity.as_ref().to_lowercase());
+        self
+    }
+
+    /// exclude identities matching this regex
+    pub fn pattern(mut self, pattern: &str) -> Result<Self, Error> {
+        self.patterns.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    pub fn is_excluded(&self, contributor: &Contributor) -> bool {
+        let identity = contributor.identity();
+        let is_bot = contributor.account_type.as_deref() == Some("Bot")
+            || contributor
+                .login
+                .as_deref()
+                .is_some_and(|login| login.ends_with("[bot]"));
+
+        (self.detect_bots && is_bot)
+            || self.deny.contains(&identity.to_lowercase())
+            || self
+                .patterns
+                .iter()
+                .any(|pattern| pattern.is_match(identity))
+    }
+
+    /// split contributors into those that are kept, and those that are excluded
+    pub fn partition(
+        &self,
+        contributors: impl IntoIterator<Item = Contributor>,
+    ) -> (Vec<Contributor>, Vec<Contributor>) {
+        contributors
+            .into_iter()
+            .partition(|contributor| !self.is_excluded(contributor))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contributor(login: &str) -> Contributor {
+        Contributor {
+            login: Some(login.into()),
+            contributions: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn detects_bot_suffix() {
+        let filter = BotFilter::default();
+        assert!(filter.is_excluded(&contributor("dependabot[bot]")));
+        assert!(filter.is_excluded(&contributor("github-actions[bot]")));
+        assert!(!filter.is_excluded(&contributor("robot")));
+    }
+
+    #[test]
+    fn detects_bot_account_type() {
+        let bot = Contributor {
+            account_type: Some("Bot".into()),
+            ..contributor("renovate")
+        };
+        assert!(BotFilter::default().is_excluded(&bot));
+        assert!(!BotFilter::none().is_excluded(&bot));
+    }
+
+    #[test]
+    fn deny_list_ignores_case() {
+        let filter = BotFilter::none().deny("Bors");
+        assert!(filter.is_excluded(&contributor("bors")));
+        assert!(!filter.is_excluded(&contributor("borsa")));
+    }
+
+    #[test]
+    fn patterns_match_logins() {
+        let filter = BotFilter::none().pattern("^ci-.*").unwrap();
+        assert!(filter.is_excluded(&contributor("ci-runner")));
+        assert!(!filter.is_excluded(&contributor("alice-ci-")));
+    }
+
+    #[test]
+    fn invalid_pattern_is_an_error() {
+        assert!(BotFilter::none().pattern("(").is_err());
+    }
+
+    #[test]
+    fn partition_keeps_order() {
+        let (kept, excluded) = BotFilter::default().partition([
+            contributor("alice"),
+            contributor("dependabot[bot]"),
+            contributor("bob"),
+        ]);
+        assert_eq!(kept, vec![contributor("alice"), contributor("bob")]);
+        assert_eq!(excluded, vec![contributor("dependabot[bot]")]);
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::{errors::Error, model::Contributor};
+
+/// Maps the alternative logins, names and emails of a person onto one canonical identity
+///
+/// Aliases can be loaded from a git `.mailmap` file, or from a TOML file of the form:
+///
+/// ```toml
+/// [aliases]
+/// "Jane Doe" = ["jdoe", "jane@example.com"]
+/// ```
+///
+/// Matching ignores case, since Github logins and emails are case insensitive
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Identities {
+    canonical: HashMap<String, String>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AliasFile {
+    aliases: HashMap<String, Vec<String>>,
+}
+
+impl Identities {
+    /// load a TOML alias file if the path ends in `.toml`, otherwise a mailmap
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let contents = std::fs::read_to_string(path)?;
+        match path.extension() {
+            Some(extension) if extension == "toml" => Self::from_toml(&contents),
+            _ => Ok(Self::from_mailmap(&contents)),
+        }
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, Error> {
+        let file: AliasFile = toml::from_str(contents)?;
+        let mut identities = Self::default();
+        for (canonical, aliases) in file.aliases {
+            for alias in aliases {
+                identities = identities.alias(alias, &canonical);
+            }
+        }
+        Ok(identities)
+    }
+
+    /// parse the entry forms described in `git help check-mailmap`, lines that don't match any of
+    /// them are skipped
+    ///
+    /// the canonical identity is the proper name if there is one, otherwise the proper email
+    pub fn from_mailmap(contents: &str) -> Self {
+        let mut identities = Self::default();
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let Some(entry) = MailmapEntry::parse(line) else {
+                continue;
+            };
+
+            let canonical = entry.proper_name.or(entry.proper_email).unwrap_or_default();
+            let aliases = [entry.proper_email, entry.commit_name, entry.commit_email];
+            for alias in aliases.into_iter().flatten() {
+                if alias != canonical {
+                    identities = identities.alias(alias, canonical);
+                }
+            }
+        }
+        identities
+    }
+
+    pub fn alias(mut self, alias: impl AsRef<str>, canonical: impl Into<String>) -> Self {
+        self.canonical
+            .insert(alias.as_ref().to_lowercase(), canonical.into());
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.canonical.is_empty()
+    }
+
+    /// the canonical form of an identity, or the identity itself if it has no alias
+    pub fn canonical<'a>(&'a self, identity: &'a str) -> &'a str {
+        self.lookup(identity).unwrap_or(identity)
+    }
+
+    /// a canonical identity followed by every alias that maps onto it
+    pub fn aliases<'a>(&'a self, canonical: &'a str) -> Vec<&'a str> {
+        let mut aliases: Vec<&str> = self
+            .canonical
+            .iter()
+            .filter(|(_, target)| target.eq_ignore_ascii_case(canonical))
+            .map(|(alias, _)| alias.as_str())
+            .filter(|alias| !alias.eq_ignore_ascii_case(canonical))
+            .collect();
+        aliases.sort_unstable();
+        aliases.insert(0, canonical);
+        aliases
+    }
+
+    fn lookup(&self, identity: &str) -> Option<&str> {
+        self.canonical
+            .get(&identity.to_lowercase())
+            .map(String::as_str)
+    }
+
+    /// the canonical identity of the first of login, name or email that has an alias
+    pub fn canonical_contributor<'a>(&'a self, contributor: &'a Contributor) -> &'a str {
+        [&contributor.login, &contributor.name, &contributor.email]
+            .into_iter()
+            .flatten()
+            .find_map(|identity| self.lookup(identity))
+            .unwrap_or_else(|| contributor.identity())
+    }
+
+    /// fold contributors that share a canonical identity into one entry, in order of first
+    /// appearance
+    ///
+    /// the canonical identity becomes the login of the merged entry, so it is what gets reported
+    pub fn merge(&self, contributors: impl IntoIterator<Item = Contributor>) -> Vec<Contributor> {
+        let mut merged: Vec<Contributor> = vec![];
+        let mut positions: HashMap<String, usize> = HashMap::new();
+
+        for contributor in contributors {
+            let canonical = self.canonical_contributor(&contributor).to_owned();
+            match positions.get(&canonical.to_lowercase()) {
+                Some(&index) => merged[index].contributions += contributor.contributions,
+                None => {
+                    positions.insert(canonical.to_lowercase(), merged.len());
+                    merged.push(Contributor {
+                        login: Some(canonical),
+                        ..contributor
+                    });
+                }
+            }
+        }
+        merged
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct MailmapEntry<'a> {
+    proper_name: Option<&'a str>,
+    proper_email: Option<&'a str>,
+    commit_name: Option<&'a str>,
+    commit_email: Option<&'a str>,
+}
+
+impl<'a> MailmapEntry<'a> {
+    /// a line is up to two `Name <email>` pairs, where either name may be omitted
+    fn parse(line: &'a str) -> Option<Self> {
+        let (first_name, first_email, rest) = split_pair(line)?;
+        let entry = match split_pair(rest) {
+            // `Proper Name <commit@email>`
+            None => Self {
+                proper_name: Some(first_name?),
+                commit_email: Some(first_email),
+                ..Default::default()
+            },
+            Some((commit_name, commit_email, _)) => Self {
+                proper_name: first_name,
+                proper_email: Some(first_email),
+                commit_name,
+                commit_email: Some(commit_email),
+            },
+        };
+        Some(entry)
+    }
+}
+
+/// split `Name <email> rest` into its parts, the name being `None` if blank
+fn split_pair(s: &str) -> Option<(Option<&str>, &str, &str)> {
+    let open = s.find('<')?;
+    let close = open + s[open..].find('>')?;
+    let name = s[..open].trim();
+    let email = s[open + 1..close].trim();
+    Some(((!name.is_empty()).then_some(name), email, &s[close + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contributor(login: &str, contributions: u64) -> Contributor {
+        Contributor {
+            login: Some(login.into()),
+            contributions,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_mailmap_forms() {
+        assert_eq!(
+            MailmapEntry::parse("Jane Doe <jane@example.com>"),
+            Some(MailmapEntry {
+                proper_name: Some("Jane Doe"),
+                commit_email: Some("jane@example.com"),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            MailmapEntry::parse("<jane@example.com> <jd@old.com>"),
+            Some(MailmapEntry {
+                proper_email: Some("jane@example.com"),
+                commit_email: Some("jd@old.com"),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            MailmapEntry::parse("Jane Doe <jane@example.com> jdoe <jd@old.com>"),
+            Some(MailmapEntry {
+                proper_name: Some("Jane Doe"),
+                proper_email: Some("jane@example.com"),
+                commit_name: Some("jdoe"),
+                commit_email: Some("jd@old.com"),
+            })
+        );
+        assert_eq!(MailmapEntry::parse("no email here"), None);
+        assert_eq!(MailmapEntry::parse("<lonely@example.com>"), None);
+    }
+
+    #[test]
+    fn mailmap_aliases() {
+        let identities = Identities::from_mailmap(
+            "# comment\n\
+             Jane Doe <jane@example.com> jdoe <jd@old.com>\n\
+             <bob@example.com> <robert@old.com>\n",
+        );
+        assert_eq!(identities.canonical("jdoe"), "Jane Doe");
+        assert_eq!(identities.canonical("JD@old.com"), "Jane Doe");
+        assert_eq!(identities.canonical("jane@example.com"), "Jane Doe");
+        assert_eq!(identities.canonical("robert@old.com"), "bob@example.com");
+        assert_eq!(identities.canonical("someone"), "someone");
+    }
+
+    #[test]
+    fn toml_aliases() {
+        let identities = Identities::from_toml(
+            r#"
+            [aliases]
+            "Jane Doe" = ["jdoe", "jane-work"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(identities.canonical("jane-work"), "Jane Doe");
+        assert_eq!(identities.canonical("JDOE"), "Jane Doe");
+        assert_eq!(
+            identities.aliases("Jane Doe"),
+            vec!["Jane Doe", "jane-work", "jdoe"]
+        );
+        assert_eq!(identities.aliases("someone"), vec!["someone"]);
+    }
+
+    #[test]
+    fn toml_rejects_unknown_keys() {
+        assert!(Identities::from_toml("[alias]\nx = [\"y\"]").is_err());
+    }
+
+    #[test]
+    fn merges_aliased_contributors() {
+        let identities = Identities::default()
+            .alias("jdoe", "Jane Doe")
+            .alias("jane-work", "Jane Doe");
+        let merged = identities.merge([
+            contributor("jdoe", 4),
+            contributor("bob", 6),
+            contributor("jane-work", 5),
+        ]);
+        assert_eq!(
+            merged,
+            vec![contributor("Jane Doe", 9), contributor("bob", 6)]
+        );
+    }
+
+    #[test]
+    fn merges_anonymous_contributors_by_email() {
+        let identities = Identities::default().alias("jane@example.com", "jdoe");
+        let anonymous = Contributor {
+            email: Some("jane@example.com".into()),
+            name: Some("Jane".into()),
+            contributions: 2,
+            ..Default::default()
+        };
+        let merged = identities.merge([contributor("jdoe", 3), anonymous]);
+        assert_eq!(merged, vec![contributor("jdoe", 5)]);
+    }
+
+    #[test]
+    fn merges_canonical_with_alias() {
+        let identities = Identities::default().alias("jdoe", "jane");
+        let merged = identities.merge([contributor("Jane", 1), contributor("jdoe", 2)]);
+        assert_eq!(merged, vec![contributor("Jane", 3)]);
+    }
+}
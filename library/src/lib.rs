@@ -13,29 +13,112 @@ extern crate error_chain;
 #[macro_use]
 extern crate log;
 
+mod activity;
+mod affiliation;
 mod args;
+mod cache;
 mod calculate;
 mod client;
+mod codeowners;
+mod config;
+mod deps;
 mod environment;
 mod errors;
+mod filter;
+mod identity;
+mod local;
 mod model;
+mod ownership;
+mod replay;
+mod retry;
+mod risk;
+mod serve;
+mod statistics;
 
 pub mod prelude {
-    pub use super::args::Args;
-    pub use super::calculate::execute_query;
-    pub use super::client::{DefaultClient, GithubClient};
-    pub use super::environment::get_api_key;
+    pub use super::activity::ActivityOptions;
+    pub use super::affiliation::Affiliations;
+    pub use super::args::{Args, CacheCommand, Command};
+    pub use super::cache::{CachedResponse, CachingClient, DiskCache, MemoryCache, ResponseCache};
+    pub use super::calculate::{
+        execute_queries_with, execute_query, execute_query_with, AnalysisOptions,
+        ContributionMetric, IntoQueries, OutputFormat, SortBy, Weighting, DEFAULT_THRESHOLD,
+    };
+    pub use super::client::{DefaultClient, DefaultClientBuilder, GithubClient};
+    pub use super::codeowners::CodeOwners;
+    pub use super::config::Config;
+    pub use super::deps::{Dependency, Registry, DEFAULT_REGISTRY_URL};
+    pub use super::environment::{get_api_key, get_api_key_with, AuthOptions, AuthSource};
     pub use super::errors::*;
+    pub use super::filter::BotFilter;
+    pub use super::identity::Identities;
+    pub use super::local::{LocalGitClient, LocalGitOptions};
     pub use super::model::*;
+    pub use super::ownership::OwnershipOptions;
+    pub use super::replay::{RecordingClient, ReplayClient};
+    pub use super::retry::RetryPolicy;
+    pub use super::risk::RiskWeights;
+    pub use super::serve::serve;
 }
 
 #[cfg(test)]
 mod tests {
     use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
 
     pub(crate) fn test_file_path(s: impl AsRef<str>) -> PathBuf {
         let base = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         let test_dir = base.join("test");
         test_dir.join(s.as_ref())
     }
+
+    /// a canned HTTP response for [`serve`]
+    #[derive(Clone)]
+    pub(crate) struct MockResponse {
+        pub status: u16,
+        pub headers: Vec<(&'static str, &'static str)>,
+        pub body: &'static str,
+    }
+
+    /// serve one response per connection, in order, returning the base URL of the server and the
+    /// heads of the requests it received
+    pub(crate) async fn serve(responses: Vec<MockResponse>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+
+        let received = requests.clone();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut head = vec![];
+                while !head.ends_with(b"\r\n\r\n") {
+                    let mut byte = [0];
+                    stream.read_exact(&mut byte).await.unwrap();
+                    head.push(byte[0]);
+                }
+                received
+                    .lock()
+                    .unwrap()
+                    .push(String::from_utf8(head).unwrap());
+
+                let mut raw = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+                    response.status,
+                    response.body.len()
+                );
+                for (name, value) in response.headers {
+                    raw.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                raw.push_str("\r\n");
+                raw.push_str(response.body);
+                stream.write_all(raw.as_bytes()).await.unwrap();
+            }
+        });
+
+        (url, requests)
+    }
 }
@@ -0,0 +1,459 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use git2::{ErrorCode, Repository as GitRepository, Sort};
+
+use crate::{client::GithubClient, codeowners::CODEOWNERS_PATHS, errors::Error, model::*};
+
+/// Controls which commits are counted when walking a local history
+///
+/// The date range is set with `--local_since`/`--local_until`, apart from `--since`/`--until`,
+/// which only set the window passed to [`GithubClient::list_commits`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LocalGitOptions {
+    /// ignore commits made before this time
+    pub since: Option<DateTime<Utc>>,
+    /// ignore commits made after this time
+    pub until: Option<DateTime<Utc>>,
+    /// skip commits with more than one parent
+    pub exclude_merges: bool,
+}
+
+/// A client backed by a directory of local clones, so no network access is required
+///
+/// Each direct child of `root` that is a git repository is treated as a [`Repository`], owned by
+/// the name of the root directory
+#[derive(Debug, Clone)]
+pub struct LocalGitClient {
+    root: PathBuf,
+    options: LocalGitOptions,
+}
+
+impl LocalGitClient {
+    pub fn create(root: impl Into<PathBuf>, options: LocalGitOptions) -> Self {
+        Self {
+            root: root.into(),
+            options,
+        }
+    }
+
+    fn owner(&self) -> Owner {
+        let login = self
+            .root
+            .canonicalize()
+            .ok()
+            .and_then(|path| {
+                path.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| "local".into());
+        Owner { login }
+    }
+
+    fn discover_repositories(&self) -> Result<Vec<Repository>, Error> {
+        let mut names = vec![];
+        for entry in std::fs::read_dir(&self.root)? {
+            let path = entry?.path();
+            if path.is_dir() && GitRepository::open(&path).is_ok() {
+                names.push(path.file_name().unwrap().to_string_lossy().into_owned());
+            } else {
+                debug!("skipping non-repository path: {:?}", path);
+            }
+        }
+        names.sort();
+
+        let owner = self.owner();
+        let repos = names
+            .into_iter()
+            .map(|name| Repository {
+                name,
+                owner: owner.clone(),
+                ..Default::default()
+            })
+            .collect();
+        Ok(repos)
+    }
+
+    fn includes(&self, commit: &git2::Commit) -> bool {
+        if self.options.exclude_merges && commit.parent_count() > 1 {
+            return false;
+        }
+
+        let time = commit.time().seconds();
+        let after_since = self
+            .options
+            .since
+            .is_none_or(|since| time >= since.timestamp());
+        let before_until = self
+            .options
+            .until
+            .is_none_or(|until| time <= until.timestamp());
+        after_since && before_until
+    }
+
+    /// every included commit reachable from HEAD, newest first
+    fn walk_commits(&self, path: &Path) -> Result<Vec<Commit>, Error> {
+        let repo = GitRepository::open(path)?;
+        if !has_commits(&repo)? {
+            return Ok(vec![]);
+        }
+        let mut walk = repo.revwalk()?;
+        walk.set_sorting(Sort::TIME)?;
+        walk.push_head()?;
+
+        // the repository's own .mailmap, if any, is applied to every author
+        let mailmap = repo.mailmap()?;
+        let mut commits = vec![];
+        for oid in walk {
+            let commit = repo.find_commit(oid?)?;
+            if !self.includes(&commit) {
+                continue;
+            }
+
+            let author = commit.author_with_mailmap(&mailmap)?;
+            let timestamp =
+                DateTime::from_timestamp(commit.time().seconds(), 0).unwrap_or_default();
+            commits.push(Commit {
+                login: None,
+                name: author.name().map(str::to_owned),
+                email: author.email().map(str::to_owned),
+                account_type: None,
+                timestamp,
+            });
+        }
+        Ok(commits)
+    }
+
+    /// lines changed per file in every included commit, compared with its first parent
+    ///
+    /// merges are always skipped, since their changes were already made by the merged commits
+    fn diff_commits(&self, path: &Path) -> Result<Vec<FileChange>, Error> {
+        let repo = GitRepository::open(path)?;
+        if !has_commits(&repo)? {
+            return Ok(vec![]);
+        }
+        let mut walk = repo.revwalk()?;
+        walk.push_head()?;
+
+        let mailmap = repo.mailmap()?;
+        let mut changes = vec![];
+        for oid in walk {
+            let commit = repo.find_commit(oid?)?;
+            if commit.parent_count() > 1 || !self.includes(&commit) {
+                continue;
+            }
+
+            let author = commit.author_with_mailmap(&mailmap)?;
+            let parent_tree = match commit.parent_count() {
+                0 => None,
+                _ => Some(commit.parent(0)?.tree()?),
+            };
+            let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+            for index in 0..diff.deltas().len() {
+                let Some(patch) = git2::Patch::from_diff(&diff, index)? else {
+                    continue; // binary files have no lines
+                };
+                let (_, additions, deletions) = patch.line_stats()?;
+                let Some(file) = patch.delta().new_file().path() else {
+                    continue;
+                };
+                changes.push(FileChange {
+                    path: file.to_string_lossy().into_owned(),
+                    author: Contributor {
+                        name: author.name().map(str::to_owned),
+                        email: author.email().map(str::to_owned),
+                        contributions: (additions + deletions) as u64,
+                        ..Default::default()
+                    },
+                });
+            }
+        }
+        Ok(changes)
+    }
+
+    fn count_contributions(&self, path: &Path) -> Result<Vec<Contributor>, Error> {
+        // authors are grouped by name, like `git shortlog`
+        Ok(Contributor::tally(
+            self.walk_commits(path)?.iter().map(Commit::author),
+        ))
+    }
+}
+
+#[async_trait]
+impl GithubClient for LocalGitClient {
+    async fn list_repositories<Q>(&self, query: Q) -> Result<Vec<Repository>, Error>
+    where
+        Q: Into<Query> + Send,
+    {
+        let query = query.into();
+        let mut repos = self.discover_repositories()?;
+        repos.truncate(query.limit as usize);
+        Ok(repos)
+    }
+
+    async fn list_contributors(&self, repository: &Repository) -> Result<Vec<Contributor>, Error> {
+        let path = self.root.join(&repository.name);
+        debug!("walking commit log of {:?}", path);
+        self.count_contributions(&path)
+    }
+
+    async fn list_commits(
+        &self,
+        repository: &Repository,
+        window: Window,
+    ) -> Result<Vec<Commit>, Error> {
+        let path = self.root.join(&repository.name);
+        let commits = self.walk_commits(&path)?;
+        Ok(commits
+            .into_iter()
+            .filter(|commit| window.contains(commit.timestamp))
+            .collect())
+    }
+
+    async fn list_file_changes(&self, repository: &Repository) -> Result<Vec<FileChange>, Error> {
+        self.diff_commits(&self.root.join(&repository.name))
+    }
+
+    /// read from the committed tree at HEAD rather than the working directory
+    async fn get_codeowners(&self, repository: &Repository) -> Result<Option<String>, Error> {
+        let repo = GitRepository::open(self.root.join(&repository.name))?;
+        let tree = repo.head()?.peel_to_tree()?;
+        for path in CODEOWNERS_PATHS {
+            let Ok(entry) = tree.get_path(Path::new(path)) else {
+                continue;
+            };
+            let blob = entry.to_object(&repo)?.peel_to_blob()?;
+            return Ok(Some(String::from_utf8_lossy(blob.content()).into_owned()));
+        }
+        Ok(None)
+    }
+}
+
+/// whether HEAD points at a commit, which it doesn't in a freshly initialized repository
+fn has_commits(repo: &GitRepository) -> Result<bool, Error> {
+    match repo.head() {
+        Ok(_) => Ok(true),
+        Err(e) if matches!(e.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use git2::{Signature, Time};
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn commit(
+        repo: &GitRepository,
+        author: &str,
+        timestamp: i64,
+        parents: &[&git2::Commit],
+    ) -> git2::Oid {
+        let signature = Signature::new(
+            author,
+            &format!("{}@example.com", author),
+            &Time::new(timestamp, 0),
+        )
+        .unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            "message",
+            &tree,
+            parents,
+        )
+        .unwrap()
+    }
+
+    /// alice and bob each commit once, then alice merges the two
+    fn make_repo(dir: &Path) {
+        let repo = GitRepository::init(dir).unwrap();
+        let first = commit(&repo, "alice", 1_000, &[]);
+        let first = repo.find_commit(first).unwrap();
+        let second = commit(&repo, "bob", 2_000, &[&first]);
+        let second = repo.find_commit(second).unwrap();
+        commit(&repo, "alice", 3_000, &[&second, &first]);
+    }
+
+    fn author(name: &str, contributions: u64) -> Contributor {
+        Contributor {
+            name: Some(name.into()),
+            email: Some(format!("{}@example.com", name)),
+            contributions,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn counts_commits_per_author() {
+        let dir = TempDir::new().unwrap();
+        make_repo(dir.path());
+        let client = LocalGitClient::create(dir.path(), LocalGitOptions::default());
+        let contributors = client.count_contributions(dir.path()).unwrap();
+        assert_eq!(contributors, vec![author("alice", 2), author("bob", 1)]);
+    }
+
+    #[test]
+    fn empty_repository_has_no_commits() {
+        let dir = TempDir::new().unwrap();
+        GitRepository::init(dir.path()).unwrap();
+        let client = LocalGitClient::create(dir.path(), LocalGitOptions::default());
+        assert!(client.count_contributions(dir.path()).unwrap().is_empty());
+        assert!(client.diff_commits(dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn can_exclude_merges() {
+        let dir = TempDir::new().unwrap();
+        make_repo(dir.path());
+        let options = LocalGitOptions {
+            exclude_merges: true,
+            ..Default::default()
+        };
+        let client = LocalGitClient::create(dir.path(), options);
+        let contributors = client.count_contributions(dir.path()).unwrap();
+        let total: u64 = contributors.iter().map(|c| c.contributions).sum();
+        assert_eq!(total, 2);
+    }
+
+    #[test]
+    fn respects_date_range() {
+        let dir = TempDir::new().unwrap();
+        make_repo(dir.path());
+        let options = LocalGitOptions {
+            since: Some(Utc.timestamp_opt(1_500, 0).unwrap()),
+            until: Some(Utc.timestamp_opt(2_500, 0).unwrap()),
+            ..Default::default()
+        };
+        let client = LocalGitClient::create(dir.path(), options);
+        let contributors = client.count_contributions(dir.path()).unwrap();
+        assert_eq!(contributors, vec![author("bob", 1)]);
+    }
+
+    #[test]
+    fn applies_repository_mailmap() {
+        let dir = TempDir::new().unwrap();
+        make_repo(dir.path());
+        std::fs::write(dir.path().join(".mailmap"), "alice <bob@example.com>\n").unwrap();
+
+        let client = LocalGitClient::create(dir.path(), LocalGitOptions::default());
+        let contributors = client.count_contributions(dir.path()).unwrap();
+        assert_eq!(contributors, vec![author("alice", 3)]);
+    }
+
+    #[test]
+    fn discovers_only_git_directories() {
+        let root = TempDir::new().unwrap();
+        make_repo(&root.path().join("b_repo"));
+        make_repo(&root.path().join("a_repo"));
+        std::fs::create_dir(root.path().join("not_a_repo")).unwrap();
+        std::fs::write(root.path().join("file"), "").unwrap();
+
+        let client = LocalGitClient::create(root.path(), LocalGitOptions::default());
+        let names: Vec<_> = client
+            .discover_repositories()
+            .unwrap()
+            .into_iter()
+            .map(|repo| repo.name)
+            .collect();
+        assert_eq!(names, vec!["a_repo", "b_repo"]);
+    }
+
+    #[tokio::test]
+    async fn lists_commits_within_window() {
+        let root = TempDir::new().unwrap();
+        make_repo(&root.path().join("repo"));
+        let client = LocalGitClient::create(root.path(), LocalGitOptions::default());
+        let repo = Repository {
+            name: "repo".into(),
+            owner: client.owner(),
+            ..Default::default()
+        };
+        let window = Window {
+            since: Some(Utc.timestamp_opt(1_500, 0).unwrap()),
+            until: None,
+        };
+
+        let commits = client.list_commits(&repo, window).await.unwrap();
+        let authors: Vec<_> = commits
+            .iter()
+            .map(|c| c.author().identity().to_owned())
+            .collect();
+        assert_eq!(authors, vec!["alice", "bob"]);
+        assert_eq!(commits[1].timestamp, Utc.timestamp_opt(2_000, 0).unwrap());
+    }
+
+    #[tokio::test]
+    async fn reads_committed_codeowners() {
+        let root = TempDir::new().unwrap();
+        let dir = root.path().join("repo");
+        let repo = GitRepository::init(&dir).unwrap();
+        std::fs::create_dir(dir.join("docs")).unwrap();
+        std::fs::write(dir.join("docs/CODEOWNERS"), "* @alice\n").unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("docs/CODEOWNERS")).unwrap();
+        index.write().unwrap();
+        commit(&repo, "alice", 1_000, &[]);
+        // uncommitted files aren't on the default branch yet
+        std::fs::write(dir.join("CODEOWNERS"), "* @bob\n").unwrap();
+
+        let client = LocalGitClient::create(root.path(), LocalGitOptions::default());
+        let repository = Repository {
+            name: "repo".into(),
+            owner: client.owner(),
+            ..Default::default()
+        };
+        let codeowners = client.get_codeowners(&repository).await.unwrap();
+        assert_eq!(codeowners.as_deref(), Some("* @alice\n"));
+    }
+
+    #[test]
+    fn diffs_files_per_author() {
+        let dir = TempDir::new().unwrap();
+        let repo = GitRepository::init(dir.path()).unwrap();
+        let write = |path: &str, contents: &str| {
+            let full = dir.path().join(path);
+            std::fs::create_dir_all(full.parent().unwrap()).unwrap();
+            std::fs::write(full, contents).unwrap();
+            let mut index = repo.index().unwrap();
+            index.add_path(Path::new(path)).unwrap();
+            index.write().unwrap();
+        };
+
+        write("src/a.rs", "one\ntwo\nthree\n");
+        let first = commit(&repo, "alice", 1_000, &[]);
+        let first = repo.find_commit(first).unwrap();
+        write("src/a.rs", "one\n2\nthree\n");
+        write("docs/b.md", "hello\n");
+        commit(&repo, "bob", 2_000, &[&first]);
+
+        let client = LocalGitClient::create(dir.path(), LocalGitOptions::default());
+        let mut changes: Vec<_> = client
+            .diff_commits(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|c| {
+                (
+                    c.path,
+                    c.author.identity().to_owned(),
+                    c.author.contributions,
+                )
+            })
+            .collect();
+        changes.sort();
+        assert_eq!(
+            changes,
+            vec![
+                ("docs/b.md".into(), "bob".into(), 1),
+                ("src/a.rs".into(), "alice".into(), 3),
+                ("src/a.rs".into(), "bob".into(), 2),
+            ]
+        );
+    }
+}
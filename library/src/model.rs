@@ -1,29 +1,322 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{DateTime, Utc};
 use microtype::SecretMicrotype;
 
 secret_microtype!(String => ApiKey);
 
-#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct Repository {
     pub name: String,
     pub owner: Owner,
+    #[serde(flatten)]
+    pub health: RepositoryHealth,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+/// `owner/name`, as a repository is named on the command line
+impl std::str::FromStr for Repository {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('/') {
+            Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') => {
+                Ok(Self {
+                    name: name.into(),
+                    owner: Owner {
+                        login: owner.into(),
+                    },
+                    ..Default::default()
+                })
+            }
+            _ => Err(format!("invalid repository {:?}, expected `owner/name`", s)),
+        }
+    }
+}
+
+/// What the search API says about a repository besides its name, all empty for local
+/// repositories
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RepositoryHealth {
+    #[serde(default)]
+    pub stargazers_count: u64,
+    #[serde(default)]
+    pub forks_count: u64,
+    /// including pull requests, as Github counts them
+    #[serde(default)]
+    pub open_issues_count: u64,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub pushed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub license: Option<License>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct License {
+    pub name: String,
+    /// e.g. `MIT`, or `NOASSERTION` when Github can't tell
+    #[serde(default)]
+    pub spdx_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct Owner {
     pub login: String,
 }
 
-#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+/// Someone who has committed to a repository
+///
+/// Github users have a login, while anonymous contributors (commits from emails not linked to an
+/// account) and authors from local repositories only have a name and email
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Contributor {
-    pub login: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub login: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
     pub contributions: u64,
+    /// the Github account type, e.g. `User` or `Bot`
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub account_type: Option<String>,
+}
+
+impl Contributor {
+    /// the login if there is one, otherwise the name or email
+    pub fn identity(&self) -> &str {
+        self.login
+            .as_deref()
+            .or(self.name.as_deref())
+            .or(self.email.as_deref())
+            .unwrap_or("anonymous")
+    }
+
+    /// add up the contributions of entries with the same identity, ordered like the Github API
+    /// with the most contributions first
+    pub fn tally(contributions: impl IntoIterator<Item = Contributor>) -> Vec<Contributor> {
+        let mut contributors: Vec<Contributor> = vec![];
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for contributor in contributions {
+            match positions.get(contributor.identity()) {
+                Some(&index) => contributors[index].contributions += contributor.contributions,
+                None => {
+                    positions.insert(contributor.identity().to_owned(), contributors.len());
+                    contributors.push(contributor);
+                }
+            }
+        }
+
+        contributors.sort_by(|a, b| {
+            b.contributions
+                .cmp(&a.contributions)
+                .then_with(|| a.identity().cmp(b.identity()))
+        });
+        contributors
+    }
+}
+
+/// A single commit, with the same identity fields as a [`Contributor`]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Commit {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub login: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub account_type: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Commit {
+    /// the author of this commit, as a contributor with a single contribution
+    pub fn author(&self) -> Contributor {
+        Contributor {
+            login: self.login.clone(),
+            name: self.name.clone(),
+            email: self.email.clone(),
+            contributions: 1,
+            account_type: self.account_type.clone(),
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// The parts of a Github user profile used for affiliation
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Profile {
+    pub login: String,
+    /// free text, e.g. `@github` or `GitHub, Inc.`
+    pub company: Option<String>,
+}
+
+/// Lines added plus deleted in one file by one author, the contributions of `author` being the
+/// number of lines
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FileChange {
+    pub path: String,
+    pub author: Contributor,
+}
+
+/// A range of time to look at commits in, unbounded where `None`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Window {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl Window {
+    pub fn contains(&self, timestamp: DateTime<Utc>) -> bool {
+        self.since.is_none_or(|since| timestamp >= since)
+            && self.until.is_none_or(|until| timestamp <= until)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
 pub struct RepositorySummary {
     pub repo_name: String,
     pub lead_contributor: String,
     pub percentage: f64,
+    /// others with exactly the lead contributor's share, who come after it alphabetically
+    pub co_leads: Vec<String>,
+    /// what was counted to work out the percentage, e.g. commits
+    pub metric: String,
+    /// every contributor, largest share first
+    pub top: Vec<RankedContributor>,
+    /// identities left out of the calculation, e.g. bots
+    pub excluded: Vec<String>,
+    /// the lead contributor within the analysis window, if one was requested and had any commits
+    pub recent: Option<RecentSummary>,
+    /// how the contributions are spread across everyone, not just the lead
+    pub concentration: Concentration,
+    /// directories mostly written by one person, if ownership analysis was requested
+    pub hotspots: Vec<Hotspot>,
+    /// how CODEOWNERS compares with recent commits, if requested and the repo has the file
+    pub code_owners: Option<OwnerFindings>,
+    /// the affiliation with the most contributions, if requested and any were known
+    pub organization: Option<OrganizationSummary>,
+    /// when the lead contributor last committed, only looked up for repos with a bus factor of 1
+    pub lead_activity: Option<LeadActivity>,
+    pub health: RepositoryHealth,
+    /// when the latest release was published, if requested and there is one
+    pub latest_release: Option<DateTime<Utc>>,
+    /// open pull requests without a review yet, if requested
+    pub pulls_awaiting_review: Option<u64>,
+    pub risk: RiskScore,
+    /// whether there was nothing to count, e.g. because only bots contributed, in which case the
+    /// lead contributor is empty
+    pub no_data: bool,
+}
+
+/// How urgently a repository needs attention, from 0 to 100
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct RiskScore {
+    pub score: f64,
+    pub components: Vec<RiskComponent>,
+}
+
+/// One part of a risk score, and why it scored what it did
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct RiskComponent {
+    pub name: String,
+    pub weight: f64,
+    /// from 0 for no risk to 1, `None` when it wasn't looked up, leaving it out of the score
+    pub value: Option<f64>,
+    /// what this component added to the score
+    pub points: f64,
+    pub reason: String,
+}
+
+/// How recently the lead contributor of a repository committed to it
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct LeadActivity {
+    pub last_commit: Option<DateTime<Utc>>,
+    pub status: ActivityStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityStatus {
+    Active,
+    Dormant,
+    Departed,
+    /// no commit by the lead could be found, e.g. when they are only known by name
+    Unknown,
+}
+
+impl fmt::Display for ActivityStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Active => "active",
+            Self::Dormant => "dormant",
+            Self::Departed => "departed",
+            Self::Unknown => "unknown",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The share of contributions made by people affiliated with one company or organization
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct OrganizationSummary {
+    pub lead_organization: String,
+    pub percentage: f64,
+    /// the share of contributions whose affiliation isn't known
+    pub unaffiliated: f64,
+}
+
+/// What a repository's CODEOWNERS file says about who it depends on
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct OwnerFindings {
+    pub single_owner_paths: Vec<SingleOwnerPath>,
+    /// people listed as owners without any recent commits
+    pub inactive_owners: Vec<String>,
+}
+
+/// A CODEOWNERS pattern with only one owner, who may be a person or a team
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct SingleOwnerPath {
+    pub pattern: String,
+    pub owner: String,
+}
+
+/// A directory where one author changed more than the threshold share of the lines
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Hotspot {
+    pub path: String,
+    pub owner: String,
+    pub percentage: f64,
+    /// lines changed in the directory by everyone
+    pub lines: u64,
+}
+
+/// Statistics over the full contributor distribution, see [`Concentration::of`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize)]
+pub struct Concentration {
+    pub gini: f64,
+    /// Herfindahl-Hirschman index
+    pub hhi: f64,
+    /// Shannon entropy, in bits
+    pub entropy: f64,
+}
+
+/// One contributor's place in a repository
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct RankedContributor {
+    pub identity: String,
+    /// how much was counted for this contributor, fractional when weighted
+    pub contributions: f64,
+    pub share: f64,
+}
+
+/// The lead contributor's share of the commits made within a [`Window`]
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct RecentSummary {
+    pub lead_contributor: String,
+    pub percentage: f64,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -46,6 +339,16 @@ mod tests {
         assert!(!debug.contains("asdf"));
     }
 
+    #[test]
+    fn parses_repository_names() {
+        let repo: Repository = "rust-lang/rust".parse().unwrap();
+        assert_eq!(repo.owner.login, "rust-lang");
+        assert_eq!(repo.name, "rust");
+        for invalid in ["rust", "/rust", "rust-lang/", "a/b/c"] {
+            assert!(invalid.parse::<Repository>().is_err(), "{}", invalid);
+        }
+    }
+
     #[test]
     fn deserialize_test() {
         let repo: Repository =
@@ -56,18 +359,119 @@ mod tests {
                 name: "name".into(),
                 owner: Owner {
                     login: "owner".into()
-                }
+                },
+                ..Default::default()
             }
         );
 
+        let repo: Repository = from_str(
+            r#"{
+                "name": "name",
+                "owner": {"login": "owner"},
+                "stargazers_count": 120,
+                "forks_count": 8,
+                "open_issues_count": 3,
+                "archived": true,
+                "pushed_at": "2021-03-01T10:00:00Z",
+                "license": {"key": "mit", "name": "MIT License", "spdx_id": "MIT"}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(repo.health.stargazers_count, 120);
+        assert_eq!(repo.health.forks_count, 8);
+        assert_eq!(repo.health.open_issues_count, 3);
+        assert!(repo.health.archived);
+        assert_eq!(
+            repo.health.pushed_at,
+            Some("2021-03-01T10:00:00Z".parse().unwrap())
+        );
+        assert_eq!(repo.health.license.unwrap().spdx_id.as_deref(), Some("MIT"));
+
         let contributor: Contributor =
             from_str(r#"{"login": "login", "contributions": 53}"#).unwrap();
         assert_eq!(
             contributor,
             Contributor {
-                login: "login".into(),
+                login: Some("login".into()),
                 contributions: 53,
+                ..Default::default()
             }
         );
     }
+
+    #[test]
+    fn deserialize_account_type() {
+        let contributor: Contributor =
+            from_str(r#"{"login": "dependabot[bot]", "contributions": 5, "type": "Bot"}"#).unwrap();
+        assert_eq!(contributor.account_type.as_deref(), Some("Bot"));
+    }
+
+    #[test]
+    fn deserialize_anonymous_contributor() {
+        let contributor: Contributor = from_str(
+            r#"{"email": "jane@example.com", "name": "Jane", "type": "Anonymous", "contributions": 2}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            contributor,
+            Contributor {
+                login: None,
+                name: Some("Jane".into()),
+                email: Some("jane@example.com".into()),
+                contributions: 2,
+                account_type: Some("Anonymous".into()),
+            }
+        );
+    }
+
+    fn commit(login: &str) -> Commit {
+        Commit {
+            login: Some(login.into()),
+            name: None,
+            email: None,
+            account_type: None,
+            timestamp: "2021-01-01T00:00:00Z".parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn tally_counts_commits_per_author() {
+        let commits = [commit("b"), commit("a"), commit("b"), commit("c")];
+        let counts: Vec<_> = Contributor::tally(commits.iter().map(Commit::author))
+            .into_iter()
+            .map(|c| (c.login.unwrap(), c.contributions))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![("b".into(), 2), ("a".into(), 1), ("c".into(), 1)]
+        );
+    }
+
+    #[test]
+    fn window_contains() {
+        let window = Window {
+            since: Some("2021-01-01T00:00:00Z".parse().unwrap()),
+            until: Some("2021-12-31T00:00:00Z".parse().unwrap()),
+        };
+        assert!(window.contains("2021-06-01T00:00:00Z".parse().unwrap()));
+        assert!(window.contains("2021-01-01T00:00:00Z".parse().unwrap()));
+        assert!(!window.contains("2020-06-01T00:00:00Z".parse().unwrap()));
+        assert!(!window.contains("2022-06-01T00:00:00Z".parse().unwrap()));
+        assert!(Window::default().contains("1970-01-01T00:00:00Z".parse().unwrap()));
+    }
+
+    #[test]
+    fn contributor_identity() {
+        let mut contributor = Contributor {
+            email: Some("jane@example.com".into()),
+            ..Default::default()
+        };
+        assert_eq!(contributor.identity(), "jane@example.com");
+
+        contributor.name = Some("Jane".into());
+        assert_eq!(contributor.identity(), "Jane");
+
+        contributor.login = Some("jane".into());
+        assert_eq!(contributor.identity(), "jane");
+    }
 }
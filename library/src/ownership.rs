@@ -0,0 +1,170 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    filter::BotFilter,
+    identity::Identities,
+    model::{FileChange, Hotspot},
+};
+
+/// directories with fewer changed lines than this are too small to be worth reporting
+const MIN_LINES: u64 = 100;
+
+/// Settings for finding directories that depend on one person
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OwnershipOptions {
+    /// the share of a directory's changed lines one author needs for it to be a hotspot
+    pub threshold: f64,
+}
+
+/// lines changed per canonical author in one directory
+#[derive(Default)]
+struct Directory {
+    authors: HashMap<String, u64>,
+    total: u64,
+}
+
+impl Directory {
+    fn owner(&self) -> Option<(&str, u64)> {
+        self.authors
+            .iter()
+            .max_by(|(a, a_lines), (b, b_lines)| a_lines.cmp(b_lines).then_with(|| b.cmp(a)))
+            .map(|(author, lines)| (author.as_str(), *lines))
+    }
+}
+
+/// every directory containing `path`, outermost first, excluding the repository root
+fn directories(path: &str) -> impl Iterator<Item = &str> {
+    path.match_indices('/')
+        .map(move |(index, _)| &path[..index])
+}
+
+/// find directories where one author changed more than the threshold share of the lines
+///
+/// a hotspot inside a directory that is already a hotspot of the same owner is left out, since it
+/// adds nothing to the report
+pub fn find_hotspots(
+    changes: impl IntoIterator<Item = FileChange>,
+    options: OwnershipOptions,
+    bot_filter: &BotFilter,
+    identities: &Identities,
+) -> Vec<Hotspot> {
+    // ordered so parents come before their children
+    let mut tree: BTreeMap<String, Directory> = BTreeMap::new();
+    for change in changes {
+        if bot_filter.is_excluded(&change.author) {
+            continue;
+        }
+        let author = identities.canonical_contributor(&change.author).to_owned();
+        let lines = change.author.contributions;
+        for directory in directories(&change.path) {
+            let entry = tree.entry(directory.to_owned()).or_default();
+            *entry.authors.entry(author.clone()).or_default() += lines;
+            entry.total += lines;
+        }
+    }
+
+    let mut hotspots: Vec<Hotspot> = vec![];
+    for (path, directory) in &tree {
+        let Some((owner, lines)) = directory.owner() else {
+            continue;
+        };
+        let percentage = lines as f64 / directory.total as f64;
+        if directory.total < MIN_LINES || percentage <= options.threshold {
+            continue;
+        }
+
+        let covered = hotspots.iter().any(|hotspot| {
+            hotspot.owner == owner && path.starts_with(&format!("{}/", hotspot.path))
+        });
+        if !covered {
+            hotspots.push(Hotspot {
+                path: path.clone(),
+                owner: owner.to_owned(),
+                percentage,
+                lines: directory.total,
+            });
+        }
+    }
+    hotspots
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::Contributor;
+
+    use super::*;
+
+    const OPTIONS: OwnershipOptions = OwnershipOptions { threshold: 0.75 };
+
+    fn change(path: &str, login: &str, lines: u64) -> FileChange {
+        FileChange {
+            path: path.into(),
+            author: Contributor {
+                login: Some(login.into()),
+                contributions: lines,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn paths(hotspots: &[Hotspot]) -> Vec<(&str, &str)> {
+        hotspots
+            .iter()
+            .map(|h| (h.path.as_str(), h.owner.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn lists_parent_directories() {
+        assert_eq!(
+            directories("src/parser/lexer.rs").collect::<Vec<_>>(),
+            vec!["src", "src/parser"]
+        );
+        assert_eq!(directories("README.md").count(), 0);
+    }
+
+    #[test]
+    fn finds_single_owner_subsystem() {
+        let changes = [
+            change("src/parser/lexer.rs", "alice", 400),
+            change("src/parser/ast.rs", "bob", 50),
+            change("src/net/client.rs", "bob", 300),
+            change("src/net/server.rs", "carol", 300),
+        ];
+        let hotspots = find_hotspots(changes, OPTIONS, &BotFilter::default(), &Default::default());
+        assert_eq!(paths(&hotspots), vec![("src/parser", "alice")]);
+        assert_eq!(hotspots[0].lines, 450);
+    }
+
+    #[test]
+    fn collapses_nested_hotspots() {
+        let changes = [
+            change("lib/a/one.rs", "alice", 200),
+            change("lib/b/two.rs", "alice", 200),
+            change("lib/b/c/three.rs", "alice", 200),
+        ];
+        let hotspots = find_hotspots(changes, OPTIONS, &BotFilter::default(), &Default::default());
+        assert_eq!(paths(&hotspots), vec![("lib", "alice")]);
+    }
+
+    #[test]
+    fn ignores_small_directories() {
+        let changes = [change("docs/index.md", "alice", MIN_LINES - 1)];
+        let hotspots = find_hotspots(changes, OPTIONS, &BotFilter::default(), &Default::default());
+        assert!(hotspots.is_empty());
+    }
+
+    #[test]
+    fn applies_filters_and_aliases() {
+        let changes = [
+            change("src/lib.rs", "jdoe", 100),
+            change("src/lib.rs", "jane-work", 100),
+            change("src/lib.rs", "dependabot[bot]", 1000),
+            change("src/lib.rs", "bob", 50),
+        ];
+        let identities = Identities::default().alias("jane-work", "jdoe");
+        let hotspots = find_hotspots(changes, OPTIONS, &BotFilter::default(), &identities);
+        assert_eq!(paths(&hotspots), vec![("src", "jdoe")]);
+        assert_eq!(hotspots[0].percentage, 0.8);
+    }
+}
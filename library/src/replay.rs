@@ -0,0 +1,459 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{client::GithubClient, errors::Error, errors::ErrorKind, model::*};
+
+/// Fixtures are laid out as:
+///
+/// ```text
+/// <dir>/repositories.json
+/// <dir>/contributors/<owner>/<name>.json
+/// <dir>/commits/<owner>/<name>.json
+/// ```
+///
+/// and likewise `repository`, `line_changes`, `reviewers`, `pull_request_authors`,
+/// `file_changes`, `codeowners`, `latest_release` and `awaiting_review`, where a missing
+/// CODEOWNERS file or release is recorded as `null`
+///
+/// ```text
+/// <dir>/users/<login>.json
+/// <dir>/orgs/<login>.json
+/// <dir>/last_commit/<owner>/<name>/<author>.json
+/// ```
+fn repositories_path(dir: &Path) -> PathBuf {
+    dir.join("repositories.json")
+}
+
+fn user_path(dir: &Path, kind: &str, login: &str) -> PathBuf {
+    dir.join(kind).join(format!("{}.json", login))
+}
+
+fn repo_path(dir: &Path, kind: &str, repo: &Repository) -> PathBuf {
+    dir.join(kind)
+        .join(&repo.owner.login)
+        .join(format!("{}.json", repo.name))
+}
+
+fn last_commit_path(dir: &Path, repo: &Repository, author: &str) -> PathBuf {
+    dir.join("last_commit")
+        .join(&repo.owner.login)
+        .join(&repo.name)
+        .join(format!("{}.json", author.to_lowercase()))
+}
+
+/// Passes every call through to another client, writing each result to a fixture directory that a
+/// [`ReplayClient`] can serve later
+#[derive(Debug, Clone)]
+pub struct RecordingClient<C> {
+    inner: C,
+    dir: PathBuf,
+}
+
+impl<C: GithubClient> RecordingClient<C> {
+    pub fn new(inner: C, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            dir: dir.into(),
+        }
+    }
+
+    fn record<T: Serialize>(&self, path: &Path, value: &T) -> Result<(), Error> {
+        debug!("recording fixture: {:?}", path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(value)?)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<C: GithubClient> GithubClient for RecordingClient<C> {
+    async fn list_repositories<Q>(&self, query: Q) -> Result<Vec<Repository>, Error>
+    where
+        Q: Into<Query> + Send,
+    {
+        let repos = self.inner.list_repositories(query).await?;
+        self.record(&repositories_path(&self.dir), &repos)?;
+        Ok(repos)
+    }
+
+    async fn get_repository(&self, repository: &Repository) -> Result<Repository, Error> {
+        let found = self.inner.get_repository(repository).await?;
+        self.record(&repo_path(&self.dir, "repository", repository), &found)?;
+        Ok(found)
+    }
+
+    async fn list_contributors(&self, repository: &Repository) -> Result<Vec<Contributor>, Error> {
+        let contributors = self.inner.list_contributors(repository).await?;
+        self.record(
+            &repo_path(&self.dir, "contributors", repository),
+            &contributors,
+        )?;
+        Ok(contributors)
+    }
+
+    /// the whole history is recorded whatever the window, since replaying filters it by window
+    async fn list_commits(
+        &self,
+        repository: &Repository,
+        window: Window,
+    ) -> Result<Vec<Commit>, Error> {
+        let commits = self
+            .inner
+            .list_commits(repository, Window::default())
+            .await?;
+        self.record(&repo_path(&self.dir, "commits", repository), &commits)?;
+        Ok(commits
+            .into_iter()
+            .filter(|commit| window.contains(commit.timestamp))
+            .collect())
+    }
+
+    async fn last_commit(
+        &self,
+        repository: &Repository,
+        author: &str,
+    ) -> Result<Option<DateTime<Utc>>, Error> {
+        let last_commit = self.inner.last_commit(repository, author).await?;
+        self.record(
+            &last_commit_path(&self.dir, repository, author),
+            &last_commit,
+        )?;
+        Ok(last_commit)
+    }
+
+    async fn list_line_changes(&self, repository: &Repository) -> Result<Vec<Contributor>, Error> {
+        let contributors = self.inner.list_line_changes(repository).await?;
+        self.record(
+            &repo_path(&self.dir, "line_changes", repository),
+            &contributors,
+        )?;
+        Ok(contributors)
+    }
+
+    async fn list_reviewers(&self, repository: &Repository) -> Result<Vec<Contributor>, Error> {
+        let contributors = self.inner.list_reviewers(repository).await?;
+        self.record(
+            &repo_path(&self.dir, "reviewers", repository),
+            &contributors,
+        )?;
+        Ok(contributors)
+    }
+
+    async fn list_pull_request_authors(
+        &self,
+        repository: &Repository,
+    ) -> Result<Vec<Contributor>, Error> {
+        let contributors = self.inner.list_pull_request_authors(repository).await?;
+        let path = repo_path(&self.dir, "pull_request_authors", repository);
+        self.record(&path, &contributors)?;
+        Ok(contributors)
+    }
+
+    async fn latest_release(
+        &self,
+        repository: &Repository,
+    ) -> Result<Option<DateTime<Utc>>, Error> {
+        let release = self.inner.latest_release(repository).await?;
+        self.record(
+            &repo_path(&self.dir, "latest_release", repository),
+            &release,
+        )?;
+        Ok(release)
+    }
+
+    async fn count_pulls_awaiting_review(&self, repository: &Repository) -> Result<u64, Error> {
+        let count = self.inner.count_pulls_awaiting_review(repository).await?;
+        self.record(&repo_path(&self.dir, "awaiting_review", repository), &count)?;
+        Ok(count)
+    }
+
+    async fn get_profile(&self, login: &str) -> Result<Profile, Error> {
+        let profile = self.inner.get_profile(login).await?;
+        self.record(&user_path(&self.dir, "users", login), &profile)?;
+        Ok(profile)
+    }
+
+    async fn list_organizations(&self, login: &str) -> Result<Vec<String>, Error> {
+        let orgs = self.inner.list_organizations(login).await?;
+        self.record(&user_path(&self.dir, "orgs", login), &orgs)?;
+        Ok(orgs)
+    }
+
+    async fn get_codeowners(&self, repository: &Repository) -> Result<Option<String>, Error> {
+        let codeowners = self.inner.get_codeowners(repository).await?;
+        self.record(&repo_path(&self.dir, "codeowners", repository), &codeowners)?;
+        Ok(codeowners)
+    }
+
+    async fn list_file_changes(&self, repository: &Repository) -> Result<Vec<FileChange>, Error> {
+        let changes = self.inner.list_file_changes(repository).await?;
+        self.record(&repo_path(&self.dir, "file_changes", repository), &changes)?;
+        Ok(changes)
+    }
+
+    fn cache_scope(&self) -> String {
+        self.inner.cache_scope()
+    }
+}
+
+/// Serves results captured by a [`RecordingClient`], without network access
+///
+/// The query is only used to limit the number of recorded repositories returned, so a fixture can
+/// be replayed with a smaller `--project_count` than it was recorded with
+#[derive(Debug, Clone)]
+pub struct ReplayClient {
+    dir: PathBuf,
+}
+
+impl ReplayClient {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn replay<T: DeserializeOwned>(&self, path: &Path) -> Result<T, Error> {
+        debug!("replaying fixture: {:?}", path);
+        let contents = match fs::read_to_string(path) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                bail!(ErrorKind::MissingFixture(path.display().to_string()))
+            }
+            result => result?,
+        };
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+#[async_trait]
+impl GithubClient for ReplayClient {
+    async fn list_repositories<Q>(&self, query: Q) -> Result<Vec<Repository>, Error>
+    where
+        Q: Into<Query> + Send,
+    {
+        let mut repos: Vec<Repository> = self.replay(&repositories_path(&self.dir))?;
+        repos.truncate(query.into().limit as usize);
+        Ok(repos)
+    }
+
+    async fn get_repository(&self, repository: &Repository) -> Result<Repository, Error> {
+        self.replay(&repo_path(&self.dir, "repository", repository))
+    }
+
+    async fn list_contributors(&self, repository: &Repository) -> Result<Vec<Contributor>, Error> {
+        self.replay(&repo_path(&self.dir, "contributors", repository))
+    }
+
+    /// the window is applied again, so a fixture can be replayed with a narrower window than it was
+    /// recorded with
+    async fn list_commits(
+        &self,
+        repository: &Repository,
+        window: Window,
+    ) -> Result<Vec<Commit>, Error> {
+        let commits: Vec<Commit> = self.replay(&repo_path(&self.dir, "commits", repository))?;
+        Ok(commits
+            .into_iter()
+            .filter(|commit| window.contains(commit.timestamp))
+            .collect())
+    }
+
+    async fn last_commit(
+        &self,
+        repository: &Repository,
+        author: &str,
+    ) -> Result<Option<DateTime<Utc>>, Error> {
+        self.replay(&last_commit_path(&self.dir, repository, author))
+    }
+
+    async fn list_line_changes(&self, repository: &Repository) -> Result<Vec<Contributor>, Error> {
+        self.replay(&repo_path(&self.dir, "line_changes", repository))
+    }
+
+    async fn list_reviewers(&self, repository: &Repository) -> Result<Vec<Contributor>, Error> {
+        self.replay(&repo_path(&self.dir, "reviewers", repository))
+    }
+
+    async fn list_pull_request_authors(
+        &self,
+        repository: &Repository,
+    ) -> Result<Vec<Contributor>, Error> {
+        self.replay(&repo_path(&self.dir, "pull_request_authors", repository))
+    }
+
+    async fn list_file_changes(&self, repository: &Repository) -> Result<Vec<FileChange>, Error> {
+        self.replay(&repo_path(&self.dir, "file_changes", repository))
+    }
+
+    async fn get_codeowners(&self, repository: &Repository) -> Result<Option<String>, Error> {
+        self.replay(&repo_path(&self.dir, "codeowners", repository))
+    }
+
+    async fn latest_release(
+        &self,
+        repository: &Repository,
+    ) -> Result<Option<DateTime<Utc>>, Error> {
+        self.replay(&repo_path(&self.dir, "latest_release", repository))
+    }
+
+    async fn count_pulls_awaiting_review(&self, repository: &Repository) -> Result<u64, Error> {
+        self.replay(&repo_path(&self.dir, "awaiting_review", repository))
+    }
+
+    async fn get_profile(&self, login: &str) -> Result<Profile, Error> {
+        self.replay(&user_path(&self.dir, "users", login))
+    }
+
+    async fn list_organizations(&self, login: &str) -> Result<Vec<String>, Error> {
+        self.replay(&user_path(&self.dir, "orgs", login))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct MockClient;
+
+    fn repos() -> Vec<Repository> {
+        ["first", "second"]
+            .into_iter()
+            .map(|name| Repository {
+                name: name.into(),
+                owner: Owner {
+                    login: "owner".into(),
+                },
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[async_trait]
+    impl GithubClient for MockClient {
+        async fn list_repositories<Q>(&self, _query: Q) -> Result<Vec<Repository>, Error>
+        where
+            Q: Into<Query> + Send,
+        {
+            Ok(repos())
+        }
+
+        async fn list_contributors(
+            &self,
+            repository: &Repository,
+        ) -> Result<Vec<Contributor>, Error> {
+            Ok(vec![Contributor {
+                login: Some(format!("{}-user", repository.name)),
+                contributions: 7,
+                ..Default::default()
+            }])
+        }
+
+        async fn list_commits(
+            &self,
+            _repository: &Repository,
+            window: Window,
+        ) -> Result<Vec<Commit>, Error> {
+            Ok(commits()
+                .into_iter()
+                .filter(|commit| window.contains(commit.timestamp))
+                .collect())
+        }
+    }
+
+    fn commits() -> Vec<Commit> {
+        ["2020-01-01T00:00:00Z", "2021-01-01T00:00:00Z"]
+            .into_iter()
+            .map(|timestamp| Commit {
+                login: Some("user".into()),
+                name: None,
+                email: None,
+                account_type: None,
+                timestamp: timestamp.parse().unwrap(),
+            })
+            .collect()
+    }
+
+    fn query(limit: u32) -> Query {
+        Query {
+            limit,
+            language: "rust".into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_results() {
+        let dir = TempDir::new().unwrap();
+        let recorder = RecordingClient::new(MockClient, dir.path());
+        let recorded_repos = recorder.list_repositories(query(2)).await.unwrap();
+        let recorded_contributors = recorder.list_contributors(&repos()[1]).await.unwrap();
+
+        let replay = ReplayClient::new(dir.path());
+        assert_eq!(
+            replay.list_repositories(query(2)).await.unwrap(),
+            recorded_repos
+        );
+        assert_eq!(
+            replay.list_contributors(&repos()[1]).await.unwrap(),
+            recorded_contributors
+        );
+    }
+
+    #[tokio::test]
+    async fn replay_applies_limit() {
+        let dir = TempDir::new().unwrap();
+        RecordingClient::new(MockClient, dir.path())
+            .list_repositories(query(2))
+            .await
+            .unwrap();
+
+        let replayed = ReplayClient::new(dir.path())
+            .list_repositories(query(1))
+            .await
+            .unwrap();
+        assert_eq!(replayed, repos()[..1]);
+    }
+
+    #[tokio::test]
+    async fn records_commits_outside_the_window() {
+        let dir = TempDir::new().unwrap();
+        let recent = Window {
+            since: Some("2020-06-01T00:00:00Z".parse().unwrap()),
+            until: None,
+        };
+        let recorded = RecordingClient::new(MockClient, dir.path())
+            .list_commits(&repos()[0], recent)
+            .await
+            .unwrap();
+        assert_eq!(recorded, commits()[1..]);
+
+        let replay = ReplayClient::new(dir.path());
+        assert_eq!(
+            replay.list_commits(&repos()[0], recent).await.unwrap(),
+            recorded
+        );
+        assert_eq!(
+            replay
+                .list_commits(&repos()[0], Window::default())
+                .await
+                .unwrap(),
+            commits()
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_fixture_is_reported() {
+        let dir = TempDir::new().unwrap();
+        let result = ReplayClient::new(dir.path())
+            .list_contributors(&repos()[0])
+            .await;
+        assert!(matches!(
+            result,
+            Err(Error(ErrorKind::MissingFixture(_), _))
+        ));
+    }
+}
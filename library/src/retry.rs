@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use reqwest::{header::HeaderMap, StatusCode};
+
+const RETRY_AFTER: &str = "retry-after";
+const RATE_LIMIT_REMAINING: &str = "x-ratelimit-remaining";
+const RATE_LIMIT_RESET: &str = "x-ratelimit-reset";
+
+/// How failed API requests are retried, with exponential backoff between attempts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            initial_backoff: Duration::ZERO,
+        }
+    }
+
+    /// the delay before retry number `attempt` (starting from 0)
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff * 2u32.saturating_pow(attempt)
+    }
+
+    /// server errors and rate limiting are usually transient, anything else will fail again
+    pub(crate) fn should_retry_status(status: StatusCode) -> bool {
+        status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+    }
+
+    /// as well as transient statuses, the 403s Github answers once a rate limit is used up
+    pub(crate) fn should_retry_response(status: StatusCode, headers: &HeaderMap) -> bool {
+        Self::should_retry_status(status) || Self::is_rate_limited(status, headers)
+    }
+
+    /// whether Github refused the request because a primary or secondary rate limit is used up
+    pub(crate) fn is_rate_limited(status: StatusCode, headers: &HeaderMap) -> bool {
+        status == StatusCode::TOO_MANY_REQUESTS
+            || (status == StatusCode::FORBIDDEN && requested_delay(headers, Utc::now()).is_some())
+    }
+
+    /// the delay before retry number `attempt`, or as long as the API asked to wait
+    pub(crate) fn delay(&self, attempt: u32, headers: &HeaderMap, now: DateTime<Utc>) -> Duration {
+        requested_delay(headers, now).unwrap_or_else(|| self.backoff(attempt))
+    }
+
+    pub(crate) fn should_retry_error(error: &reqwest::Error) -> bool {
+        error.is_connect() || error.is_timeout()
+    }
+}
+
+/// `Retry-After` in seconds, or the time until `x-ratelimit-reset` once no requests remain
+fn requested_delay(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    if let Some(seconds) = header(RETRY_AFTER).and_then(|s| s.trim().parse().ok()) {
+        return Some(Duration::from_secs(seconds));
+    }
+    if header(RATE_LIMIT_REMAINING) != Some("0") {
+        return None;
+    }
+    let reset: i64 = header(RATE_LIMIT_RESET)?.trim().parse().ok()?;
+    let wait = (reset - now.timestamp()).max(0) as u64;
+    Some(Duration::from_secs(wait))
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+        };
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(2), Duration::from_millis(400));
+    }
+
+    #[test]
+    fn retries_transient_statuses_only() {
+        assert!(RetryPolicy::should_retry_status(StatusCode::BAD_GATEWAY));
+        assert!(RetryPolicy::should_retry_status(
+            StatusCode::TOO_MANY_REQUESTS
+        ));
+        // statistics that aren't ready are polled for separately
+        assert!(!RetryPolicy::should_retry_status(StatusCode::ACCEPTED));
+        assert!(!RetryPolicy::should_retry_status(StatusCode::NOT_FOUND));
+        assert!(!RetryPolicy::should_retry_status(StatusCode::OK));
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.parse().unwrap(), value.parse().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn retries_rate_limited_forbidden_only() {
+        let limited = headers(&[(RATE_LIMIT_REMAINING, "0"), (RATE_LIMIT_RESET, "0")]);
+        let forbidden = StatusCode::FORBIDDEN;
+        assert!(RetryPolicy::should_retry_response(forbidden, &limited));
+        assert!(RetryPolicy::should_retry_response(
+            forbidden,
+            &headers(&[(RETRY_AFTER, "60")])
+        ));
+        assert!(!RetryPolicy::should_retry_response(
+            forbidden,
+            &headers(&[(RATE_LIMIT_REMAINING, "10")])
+        ));
+        assert!(!RetryPolicy::should_retry_response(
+            forbidden,
+            &HeaderMap::new()
+        ));
+    }
+
+    #[test]
+    fn waits_as_long_as_the_api_asks() {
+        let policy = RetryPolicy::default();
+        let now = Utc::now();
+        let reset = (now.timestamp() + 30).to_string();
+        assert_eq!(
+            policy.delay(0, &headers(&[(RETRY_AFTER, "7")]), now),
+            Duration::from_secs(7)
+        );
+        assert_eq!(
+            policy.delay(
+                0,
+                &headers(&[(RATE_LIMIT_REMAINING, "0"), (RATE_LIMIT_RESET, &reset)]),
+                now
+            ),
+            Duration::from_secs(30)
+        );
+        assert_eq!(
+            policy.delay(
+                1,
+                &headers(&[(RATE_LIMIT_REMAINING, "5"), (RATE_LIMIT_RESET, &reset)]),
+                now
+            ),
+            policy.backoff(1)
+        );
+    }
+}
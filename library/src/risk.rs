@@ -0,0 +1,290 @@
+use std::str::FromStr;
+
+use crate::model::{ActivityStatus, RepositorySummary, RiskComponent, RiskScore};
+
+/// a repo with this many stars counts as fully critical, fewer are scaled logarithmically
+const CRITICAL_STARS: u64 = 50_000;
+/// likewise for forks, which are rarer than stars
+const CRITICAL_FORKS: u64 = 10_000;
+
+/// How much each component counts towards the risk score, relative to the others
+///
+/// Weights can be given on the command line as `concentration=2,archived=0`, or as a table in
+/// the config file; any left out keep their default.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RiskWeights {
+    /// the lead contributor's share
+    pub concentration: f64,
+    /// how long since the lead last committed, left out of the score unless `--lead_activity`
+    /// looked it up
+    pub inactivity: f64,
+    /// how many people are likely to depend on the repo, judged by its stars and forks since the
+    /// REST API has no count of dependents
+    pub criticality: f64,
+    pub archived: f64,
+}
+
+impl Default for RiskWeights {
+    fn default() -> Self {
+        Self {
+            concentration: 0.4,
+            inactivity: 0.25,
+            criticality: 0.25,
+            archived: 0.1,
+        }
+    }
+}
+
+impl FromStr for RiskWeights {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut weights = Self::default();
+        for pair in s.split(',').filter(|pair| !pair.trim().is_empty()) {
+            let (name, value) = pair.split_once('=').ok_or_else(|| {
+                format!("invalid weight {:?}, expected e.g. `archived=0.5`", pair)
+            })?;
+            let value: f64 = match value.trim().parse() {
+                Ok(value) if value >= 0.0 => value,
+                _ => {
+                    return Err(format!(
+                        "invalid weight {:?}, expected a positive number",
+                        value
+                    ))
+                }
+            };
+            let weight = match name.trim() {
+                "concentration" => &mut weights.concentration,
+                "inactivity" => &mut weights.inactivity,
+                "criticality" => &mut weights.criticality,
+                "archived" => &mut weights.archived,
+                _ => {
+                    return Err(format!(
+                        "unknown risk component {:?}, expected one of: concentration, \
+                         inactivity, criticality, archived",
+                        name
+                    ))
+                }
+            };
+            *weight = value;
+        }
+        if weights.total() <= 0.0 {
+            return Err("at least one risk weight must be above 0".into());
+        }
+        Ok(weights)
+    }
+}
+
+impl RiskWeights {
+    fn total(&self) -> f64 {
+        self.concentration + self.inactivity + self.criticality + self.archived
+    }
+
+    /// combine the components into a score from 0 to 100, each worth its weighted share of the
+    /// components that could be judged
+    pub fn score(&self, summary: &RepositorySummary) -> RiskScore {
+        let components = [
+            (self.concentration, concentration(summary)),
+            (self.inactivity, inactivity(summary)),
+            (self.criticality, criticality(summary)),
+            (self.archived, archived(summary)),
+        ];
+        let total: f64 = components
+            .iter()
+            .filter(|(_, (_, value, _))| value.is_some())
+            .map(|(weight, _)| weight)
+            .sum();
+        let components: Vec<_> = components
+            .into_iter()
+            .map(|(weight, (name, value, reason))| RiskComponent {
+                name: name.into(),
+                weight,
+                value,
+                points: match value {
+                    Some(value) if total > 0.0 => 100.0 * weight * value / total,
+                    _ => 0.0,
+                },
+                reason,
+            })
+            .collect();
+        RiskScore {
+            score: components.iter().map(|c| c.points).sum(),
+            components,
+        }
+    }
+}
+
+/// a component's name, its value if it could be judged, and why
+type Component = (&'static str, Option<f64>, String);
+
+fn concentration(summary: &RepositorySummary) -> Component {
+    let reason = format!(
+        "{} has {:.2} of the {}",
+        summary.lead_contributor, summary.percentage, summary.metric
+    );
+    let value = summary.percentage.clamp(0.0, 1.0);
+    ("concentration", Some(value), reason)
+}
+
+fn inactivity(summary: &RepositorySummary) -> Component {
+    let Some(activity) = &summary.lead_activity else {
+        return ("inactivity", None, "lead activity not looked up".into());
+    };
+    let value = match activity.status {
+        ActivityStatus::Active => 0.0,
+        // nothing found could mean either, e.g. a lead only known by their name
+        ActivityStatus::Dormant | ActivityStatus::Unknown => 0.5,
+        ActivityStatus::Departed => 1.0,
+    };
+    (
+        "inactivity",
+        Some(value),
+        format!("lead is {}", activity.status),
+    )
+}
+
+/// whichever of stars and forks suggests the wider use
+fn criticality(summary: &RepositorySummary) -> Component {
+    let scale =
+        |count: u64, critical: u64| ((count as f64).ln_1p() / (critical as f64).ln_1p()).min(1.0);
+    let stars = summary.health.stargazers_count;
+    let forks = summary.health.forks_count;
+    let value = scale(stars, CRITICAL_STARS).max(scale(forks, CRITICAL_FORKS));
+    let reason = format!("{} stars, {} forks", stars, forks);
+    ("criticality", Some(value), reason)
+}
+
+fn archived(summary: &RepositorySummary) -> Component {
+    match summary.health.archived {
+        true => (
+            "archived",
+            Some(1.0),
+            "archived, so nobody will fix it".into(),
+        ),
+        false => ("archived", Some(0.0), "not archived".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{LeadActivity, RepositoryHealth};
+
+    fn risky() -> RepositorySummary {
+        RepositorySummary {
+            lead_contributor: "user".into(),
+            percentage: 1.0,
+            metric: "commits".into(),
+            lead_activity: Some(LeadActivity {
+                last_commit: None,
+                status: ActivityStatus::Departed,
+            }),
+            health: RepositoryHealth {
+                stargazers_count: 100_000,
+                archived: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn scores_between_0_and_100() {
+        let weights = RiskWeights::default();
+        assert!((weights.score(&risky()).score - 100.0).abs() < 1e-9);
+        assert_eq!(weights.score(&RepositorySummary::default()).score, 0.0);
+    }
+
+    #[test]
+    fn explains_each_component() {
+        let score = RiskWeights::default().score(&risky());
+        let names: Vec<_> = score.components.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["concentration", "inactivity", "criticality", "archived"]
+        );
+        assert_eq!(score.components[0].reason, "user has 1.00 of the commits");
+        assert_eq!(score.components[0].points, 40.0);
+        assert_eq!(score.components[1].reason, "lead is departed");
+    }
+
+    #[test]
+    fn weights_are_relative() {
+        let weights: RiskWeights = "concentration=1,inactivity=0,criticality=0,archived=1"
+            .parse()
+            .unwrap();
+        let summary = RepositorySummary {
+            percentage: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(weights.score(&summary).score, 25.0);
+    }
+
+    #[test]
+    fn stars_are_scaled_logarithmically() {
+        let with_stars = |stars| RepositorySummary {
+            health: RepositoryHealth {
+                stargazers_count: stars,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let value = |stars| criticality(&with_stars(stars)).1.unwrap();
+        assert_eq!(value(0), 0.0);
+        assert!(value(100) > 0.4 && value(100) < 0.5);
+        assert_eq!(value(CRITICAL_STARS), 1.0);
+        assert_eq!(value(10 * CRITICAL_STARS), 1.0);
+    }
+
+    #[test]
+    fn leaves_out_inactivity_not_looked_up() {
+        let summary = RepositorySummary {
+            lead_activity: None,
+            ..risky()
+        };
+        let score = RiskWeights::default().score(&summary);
+        assert!((score.score - 100.0).abs() < 1e-9);
+        assert_eq!(score.components[1].value, None);
+        assert_eq!(score.components[1].points, 0.0);
+        assert!((score.components[0].points - 40.0 / 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn forks_count_towards_criticality() {
+        let with_forks = |forks| RepositorySummary {
+            health: RepositoryHealth {
+                stargazers_count: 10,
+                forks_count: forks,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let value = |forks| criticality(&with_forks(forks)).1.unwrap();
+        assert!(value(1_000) > value(0));
+        assert_eq!(value(CRITICAL_FORKS), 1.0);
+        assert_eq!(criticality(&with_forks(3)).2, "10 stars, 3 forks");
+    }
+
+    #[test]
+    fn parses_weights() {
+        let weights: RiskWeights = "archived=0.5".parse().unwrap();
+        assert_eq!(weights.archived, 0.5);
+        assert_eq!(weights.concentration, RiskWeights::default().concentration);
+
+        assert!("stars=1".parse::<RiskWeights>().is_err());
+        assert!("archived".parse::<RiskWeights>().is_err());
+        assert!("archived=-1".parse::<RiskWeights>().is_err());
+        assert!("concentration=0,inactivity=0,criticality=0,archived=0"
+            .parse::<RiskWeights>()
+            .is_err());
+    }
+
+    #[test]
+    fn deserializes_partial_weights() {
+        let weights: RiskWeights = toml::from_str("criticality = 1.0").unwrap();
+        assert_eq!(weights.criticality, 1.0);
+        assert_eq!(weights.archived, RiskWeights::default().archived);
+        assert!(toml::from_str::<RiskWeights>("stars = 1.0").is_err());
+    }
+}
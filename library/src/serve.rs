@@ -0,0 +1,217 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::calculate::{execute_queries_with, AnalysisOptions, OutputFormat};
+use crate::client::GithubClient;
+use crate::errors::{Error, ErrorKind};
+use crate::model::Repository;
+
+/// requests with a longer head are rejected rather than buffered
+const MAX_HEAD: usize = 8 * 1024;
+/// clients that take longer to send the request head are answered 408, so that they can't hold
+/// connections open
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Answer `GET /repos/<owner>/<name>` with the report for that repository, in the same JSON as
+/// `--format json`, until the listener fails
+pub async fn serve<C: GithubClient + 'static>(
+    listener: TcpListener,
+    client: C,
+    options: AnalysisOptions,
+) -> Result<(), Error> {
+    serve_with_timeout(listener, client, options, READ_TIMEOUT).await
+}
+
+async fn serve_with_timeout<C: GithubClient + 'static>(
+    listener: TcpListener,
+    client: C,
+    options: AnalysisOptions,
+    read_timeout: Duration,
+) -> Result<(), Error> {
+    let options = Arc::new(AnalysisOptions {
+        format: OutputFormat::Json,
+        ..options
+    });
+    info!("listening on http://{}", listener.local_addr()?);
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let (client, options) = (client.clone(), options.clone());
+        tokio::spawn(async move {
+            if let Err(e) = handle(stream, client, &options, read_timeout).await {
+                warn!("failed to answer {}: {}", peer, e);
+            }
+        });
+    }
+}
+
+async fn handle<C: GithubClient>(
+    mut stream: TcpStream,
+    client: C,
+    options: &AnalysisOptions,
+    read_timeout: Duration,
+) -> Result<(), Error> {
+    let head = match tokio::time::timeout(read_timeout, read_head(&mut stream)).await {
+        Ok(head) => head?,
+        Err(_) => return respond(&mut stream, 408, "timed out reading the request").await,
+    };
+    let Some(head) = head else {
+        return respond(&mut stream, 431, "request head too large").await;
+    };
+    let head = String::from_utf8_lossy(&head);
+    let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+    let (method, path) = (request_line.next(), request_line.next().unwrap_or_default());
+    debug!("{:?} {}", method, path);
+
+    if method != Some("GET") {
+        return respond(&mut stream, 405, "only GET is supported").await;
+    }
+    let Some(repo) = path.strip_prefix("/repos/") else {
+        return respond(&mut stream, 404, "expected /repos/<owner>/<name>").await;
+    };
+    let repo: Repository = match repo.trim_end_matches('/').parse() {
+        Ok(repo) => repo,
+        Err(e) => return respond(&mut stream, 400, &e.to_string()).await,
+    };
+
+    let mut body = vec![];
+    match execute_queries_with(client, &mut body, vec![], vec![repo], options).await {
+        Ok(()) => write_response(&mut stream, 200, &body).await,
+        Err(Error(ErrorKind::NotFound(url), _)) => {
+            respond(&mut stream, 404, &format!("not found: {}", url)).await
+        }
+        Err(e) => {
+            warn!("failed to report on {}: {}", path, e);
+            respond(&mut stream, 502, &e.to_string()).await
+        }
+    }
+}
+
+/// the request line and headers, or `None` if they are longer than [`MAX_HEAD`]
+async fn read_head(stream: &mut TcpStream) -> Result<Option<Vec<u8>>, Error> {
+    let mut head = vec![];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_HEAD {
+            return Ok(None);
+        }
+        let mut byte = [0];
+        stream.read_exact(&mut byte).await?;
+        head.push(byte[0]);
+    }
+    Ok(Some(head))
+}
+
+/// an error response, as `{"error": message}`
+async fn respond(stream: &mut TcpStream, status: u16, message: &str) -> Result<(), Error> {
+    let body = serde_json::to_vec(&serde_json::json!({ "error": message }))?;
+    write_response(stream, status, &body).await
+}
+
+async fn write_response(stream: &mut TcpStream, status: u16, body: &[u8]) -> Result<(), Error> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        431 => "Request Header Fields Too Large",
+        _ => "Bad Gateway",
+    };
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n",
+        status,
+        reason,
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body).await?;
+    Ok(stream.shutdown().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Contributor, Query};
+
+    #[derive(Clone)]
+    struct TestClient;
+
+    #[async_trait]
+    impl GithubClient for TestClient {
+        async fn list_repositories<Q>(&self, _query: Q) -> Result<Vec<Repository>, Error>
+        where
+            Q: Into<Query> + Send,
+        {
+            Ok(vec![])
+        }
+
+        async fn list_contributors(&self, repo: &Repository) -> Result<Vec<Contributor>, Error> {
+            if repo.name == "empty" {
+                return Ok(vec![]);
+            }
+            if repo.name == "missing" {
+                bail!(ErrorKind::NotFound(format!(
+                    "/repos/{}/missing",
+                    repo.owner.login
+                )));
+            }
+            Ok(vec![Contributor {
+                login: Some("lead".into()),
+                contributions: 9,
+                ..Default::default()
+            }])
+        }
+    }
+
+    async fn get(url: &str) -> (u16, serde_json::Value) {
+        let response = reqwest::get(url).await.unwrap();
+        let status = response.status().as_u16();
+        (status, response.json().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn serves_reports() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(serve(listener, TestClient, AnalysisOptions::default()));
+
+        let (status, report) = get(&format!("{}/repos/owner/name", url)).await;
+        assert_eq!(status, 200);
+        assert_eq!(report[0]["repo_name"], "name");
+        assert_eq!(report[0]["lead_contributor"], "lead");
+        assert_eq!(report[0]["bus_factor_1"], true);
+
+        let (status, report) = get(&format!("{}/repos/owner/empty", url)).await;
+        assert_eq!(status, 200);
+        assert_eq!(report[0]["no_data"], true);
+        assert_eq!(report[0]["bus_factor_1"], false);
+
+        let (status, error) = get(&format!("{}/repos/owner/missing", url)).await;
+        assert_eq!(status, 404);
+        assert!(error["error"].as_str().unwrap().contains("missing"));
+
+        assert_eq!(get(&format!("{}/repos/name", url)).await.0, 400);
+        assert_eq!(get(&format!("{}/search", url)).await.0, 404);
+    }
+
+    #[tokio::test]
+    async fn times_out_slow_requests() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let options = AnalysisOptions::default();
+        let timeout = Duration::from_millis(50);
+        tokio::spawn(serve_with_timeout(listener, TestClient, options, timeout));
+
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream
+            .write_all(b"GET /repos/owner/name HTTP/1.1\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 408 "), "{}", response);
+    }
+}
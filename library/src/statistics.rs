@@ -0,0 +1,135 @@
+use crate::model::Concentration;
+
+impl Concentration {
+    /// measure how unevenly the contributions are spread between contributors
+    pub fn of(contributions: &[f64]) -> Self {
+        Self {
+            gini: gini(contributions),
+            hhi: hhi(contributions),
+            entropy: entropy(contributions),
+        }
+    }
+}
+
+/// each contribution as a fraction of the total, empty if there is nothing to share
+fn shares(contributions: &[f64]) -> Vec<f64> {
+    let total: f64 = contributions.iter().sum();
+    if total <= 0.0 {
+        return vec![];
+    }
+    contributions.iter().map(|c| c / total).collect()
+}
+
+/// the Gini coefficient, 0 when everyone contributes equally, approaching 1 as one contributor
+/// does everything
+///
+/// uses the sorted form `2 * sum(i * x_i) / (n * sum(x)) - (n + 1) / n`, with `i` counting from 1
+fn gini(contributions: &[f64]) -> f64 {
+    let mut shares = shares(contributions);
+    if shares.is_empty() {
+        return 0.0;
+    }
+    shares.sort_by(f64::total_cmp);
+
+    let n = shares.len() as f64;
+    let weighted: f64 = shares
+        .iter()
+        .enumerate()
+        .map(|(i, share)| (i + 1) as f64 * share)
+        .sum();
+    // shares sum to 1, and rounding can push a perfectly even spread slightly negative
+    (2.0 * weighted / n - (n + 1.0) / n).max(0.0)
+}
+
+/// the Herfindahl-Hirschman index, the sum of squared shares, from `1 / n` for an even spread to
+/// 1 for a single contributor
+fn hhi(contributions: &[f64]) -> f64 {
+    shares(contributions)
+        .iter()
+        .map(|share| share * share)
+        .sum()
+}
+
+/// the Shannon entropy of the shares in bits, from 0 for a single contributor to `log2(n)` for an
+/// even spread
+fn entropy(contributions: &[f64]) -> f64 {
+    let entropy: f64 = shares(contributions)
+        .iter()
+        .filter(|&&share| share > 0.0)
+        .map(|share| -share * share.log2())
+        .sum();
+    entropy.max(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    const EPSILON: f64 = 1e-9;
+
+    #[test]
+    fn single_contributor() {
+        let concentration = Concentration::of(&[12.0]);
+        assert_eq!(concentration.gini, 0.0);
+        assert_eq!(concentration.hhi, 1.0);
+        assert_eq!(concentration.entropy, 0.0);
+    }
+
+    #[test]
+    fn distinguishes_split_leadership() {
+        // the lead share is the same, but two leads are less of a risk than one
+        let shared = Concentration::of(&[45.0, 45.0, 5.0, 5.0]);
+        let single = Concentration::of(&[45.0, 25.0, 25.0, 5.0]);
+        assert!(shared.hhi > single.hhi);
+        assert!((shared.hhi - 0.41).abs() < EPSILON);
+        assert!((shared.gini - 0.4).abs() < EPSILON);
+    }
+
+    #[test]
+    fn no_contributions() {
+        assert_eq!(Concentration::of(&[]), Concentration::default());
+        assert_eq!(Concentration::of(&[0.0, 0.0]), Concentration::default());
+    }
+
+    fn contributions() -> impl Strategy<Value = Vec<f64>> {
+        prop::collection::vec(0.0..1000.0f64, 1..50)
+            .prop_filter("needs some contributions", |c| c.iter().sum::<f64>() > 0.0)
+    }
+
+    proptest! {
+        #[test]
+        fn stays_within_bounds(contributions in contributions()) {
+            let n = contributions.len() as f64;
+            let concentration = Concentration::of(&contributions);
+            prop_assert!(concentration.gini >= 0.0);
+            prop_assert!(concentration.gini <= (n - 1.0) / n + EPSILON);
+            prop_assert!(concentration.hhi >= 1.0 / n - EPSILON);
+            prop_assert!(concentration.hhi <= 1.0 + EPSILON);
+            prop_assert!(concentration.entropy >= 0.0);
+            prop_assert!(concentration.entropy <= n.log2() + EPSILON);
+        }
+
+        #[test]
+        fn ignores_order(
+            (contributions, shuffled) in contributions()
+                .prop_flat_map(|c| (Just(c.clone()), Just(c).prop_shuffle()))
+        ) {
+            let original = Concentration::of(&contributions);
+            let shuffled = Concentration::of(&shuffled);
+            prop_assert!((original.gini - shuffled.gini).abs() < EPSILON);
+            prop_assert!((original.hhi - shuffled.hhi).abs() < EPSILON);
+            prop_assert!((original.entropy - shuffled.entropy).abs() < EPSILON);
+        }
+
+        #[test]
+        fn uniform_is_least_concentrated(value in 1.0..1000.0f64, n in 1usize..50) {
+            let concentration = Concentration::of(&vec![value; n]);
+            let n = n as f64;
+            prop_assert!(concentration.gini.abs() < EPSILON);
+            prop_assert!((concentration.hhi - 1.0 / n).abs() < EPSILON);
+            prop_assert!((concentration.entropy - n.log2()).abs() < EPSILON);
+        }
+    }
+}
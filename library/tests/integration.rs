@@ -10,6 +10,15 @@ fn expected_output_header() -> String {
     format!("{}{}\n", expected_output, "-".repeat(60))
 }
 
+/// a table row and its risk score, which only the lead's share adds to in these examples
+fn expected_row(repo: &str, user: &str, percentage: f64, risk: u32) -> String {
+    format!(
+        "{0: <20} | {1: <20} | {2:.2}\n{3: <20}   risk: {4} (concentration {4}, \
+         criticality 0, archived 0)\n",
+        repo, user, percentage, "", risk
+    )
+}
+
 #[tokio::test]
 async fn mocked_example() {
     let repos = vec![Repository {
@@ -17,18 +26,21 @@ async fn mocked_example() {
         owner: Owner {
             login: "user1".into(),
         },
+        ..Default::default()
     }];
 
     let contributors = HashMap::from_iter([(
         "repo1".into(),
         vec![
             Contributor {
-                login: "user1".into(),
+                login: Some("user1".into()),
                 contributions: 1,
+                ..Default::default()
             },
             Contributor {
-                login: "user2".into(),
+                login: Some("user2".into()),
                 contributions: 9,
+                ..Default::default()
             },
         ],
     )]);
@@ -51,10 +63,7 @@ async fn mocked_example() {
 
     let actual_output = String::from_utf8(output).unwrap();
     let expected_output = expected_output_header();
-    let expected_output = format!(
-        "{}{1: <20} | {2: <20} | {3:.2}\n",
-        expected_output, "repo1", "user2", 0.9
-    );
+    let expected_output = expected_output + &expected_row("repo1", "user2", 0.9, 48);
     assert_eq!(actual_output, expected_output);
 }
 
@@ -66,7 +75,10 @@ struct MockClient {
 
 #[async_trait::async_trait]
 impl GithubClient for MockClient {
-    async fn list_repositories<Q: Into<Query> + Send>(&self, _query: Q) -> Result<Vec<Repository>> {
+    async fn list_repositories<Q>(&self, _query: Q) -> Result<Vec<Repository>>
+    where
+        Q: Into<Query> + Send,
+    {
         Ok(self.repos.clone())
     }
 
@@ -75,34 +87,110 @@ impl GithubClient for MockClient {
     }
 }
 
+fn commit_as(repo: &git2::Repository, author: &str) {
+    let signature = git2::Signature::now(author, &format!("{}@example.com", author)).unwrap();
+    let tree_id = repo.index().unwrap().write_tree().unwrap();
+    let tree = repo.find_tree(tree_id).unwrap();
+    let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+    let parents: Vec<_> = parent.iter().collect();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "message",
+        &tree,
+        &parents,
+    )
+    .unwrap();
+}
+
+#[tokio::test]
+async fn local_example() {
+    let root = tempfile::TempDir::new().unwrap();
+
+    let solo = git2::Repository::init(root.path().join("solo")).unwrap();
+    for author in ["user1", "user1", "user1", "user1", "user2"] {
+        commit_as(&solo, author);
+    }
+
+    let shared = git2::Repository::init(root.path().join("shared")).unwrap();
+    for author in ["user1", "user2", "user3"] {
+        commit_as(&shared, author);
+    }
+
+    let client = LocalGitClient::create(root.path(), LocalGitOptions::default());
+    let mut output = vec![];
+    execute_query(
+        client,
+        &mut output,
+        Query {
+            limit: 10,
+            language: "".into(), // ignored by local client
+        },
+    )
+    .await
+    .unwrap();
+
+    let expected_output = expected_output_header();
+    let expected_output = expected_output + &expected_row("solo", "user1", 0.8, 43);
+    assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+}
+
+// replays the fixture in test/fixtures/rust, which follows the layout written by `--record`, so a
+// real run can be captured with:
+// cargo run -p binary -- --project_count 5 --language rust --record library/test/fixtures/rust
+#[tokio::test]
+async fn replayed_example() {
+    let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("test/fixtures/rust");
+    let query = Query {
+        language: "rust".into(),
+        limit: 5,
+    };
+    let mut output = vec![];
+    execute_query(ReplayClient::new(fixtures), &mut output, query)
+        .await
+        .unwrap();
+
+    let expected_output = expected_output_header();
+    let expected_output = expected_output + &expected_row("996.ICU", "996icu", 0.78, 42);
+    let expected_output = expected_output + &expected_row("ripgrep", "BurntSushi", 0.88, 47);
+
+    assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+}
+
 // note, this test may be flaky, since it relies on the internet, and could be invalidated if the
 // real-world data changes, this is more for demonstration purposes, so is excluded from CI
-// 
+//
+// it records the live run the way `--record` does, then checks that replaying the recording gives
+// the same report, so setting BUS_FACTOR_FIXTURES=library/test/fixtures/rust refreshes the
+// fixtures `replayed_example` uses
+//
 // to run this test, pass the environment variable:
 // RUSTFLAGS="--cfg e2e"
 // as well as passing the github API key via env var
 #[tokio::test]
 #[cfg_attr(not(e2e), ignore)]
 async fn live_example() {
-    let query = Query {
+    let query = || Query {
         language: "rust".into(),
         limit: 5,
     };
-    let mut output = vec![];
+    let scratch = tempfile::TempDir::new().unwrap();
+    let fixtures = match std::env::var_os("BUS_FACTOR_FIXTURES") {
+        Some(dir) => std::path::PathBuf::from(dir),
+        None => scratch.path().to_owned(),
+    };
     let api_key = get_api_key(&None).unwrap();
-    let client = DefaultClient::create(api_key);
-    execute_query(client, &mut output, query).await.unwrap();
+    let client = RecordingClient::new(DefaultClient::create(api_key).unwrap(), &fixtures);
+    let mut live = vec![];
+    execute_query(client, &mut live, query()).await.unwrap();
 
-    let expected_output = expected_output_header();
-    let expected_output = format!(
-        "{}{1: <20} | {2: <20} | {3:.2}\n",
-        expected_output, "996.ICU", "996icu", 0.78
-    );
-    let expected_output = format!(
-        "{}{1: <20} | {2: <20} | {3:.2}\n",
-        expected_output, "ripgrep", "BurntSushi", 0.88
+    let mut replayed = vec![];
+    execute_query(ReplayClient::new(&fixtures), &mut replayed, query())
+        .await
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(replayed).unwrap(),
+        String::from_utf8(live).unwrap()
     );
-
-    assert_eq!(String::from_utf8(output).unwrap(), expected_output);
 }
-